@@ -0,0 +1,38 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::Position;
+
+/// The kind of a malformed construct found in the input HTML.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DiagnosticKind {
+    /// An attribute appears more than once in a start tag, e.g. `<a id=x id=y>`.
+    DuplicateAttribute,
+    /// An attribute has `=` but no value, e.g. `<a href=>`.
+    MissingAttributeValue,
+    /// `</` is not followed by a tag name, e.g. `</>` or `</ a>`.
+    StrayEndTagOpen,
+    /// A tag is not terminated at the end of the input, e.g. `<a href=x`.
+    UnterminatedTag,
+    /// A comment is not terminated at the end of the input, e.g. `<!-- x`.
+    UnterminatedComment,
+    /// A raw text element (e.g. `script`) or an element whose content is preserved (e.g. `pre`) is not closed at the end of the input.
+    UnclosedElement,
+}
+
+/// A malformed construct found in the input HTML. The minification is not affected.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// The kind of the construct.
+    pub kind:     DiagnosticKind,
+    /// The position where the construct starts.
+    pub position: Position,
+    /// A human-readable description of the construct.
+    pub message:  String,
+}
+
+impl Display for Diagnostic {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_fmt(format_args!("{} at {}", self.message, self.position))
+    }
+}
@@ -0,0 +1,268 @@
+use std::{error::Error, fmt::Write};
+
+use minifier::{css, js};
+
+/// The kind of the element which the code is embedded in.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EmbeddedCodeKind {
+    /// The content of a `script` element.
+    Script,
+    /// The content of a `style` element.
+    Style,
+}
+
+/// Implement this trait to minify the code embedded in `script` and `style` elements. An implementation can be registered for MIME types by `HTMLMinifierHelper::add_embedded_minifier`.
+pub trait EmbeddedMinifier: Send + Sync {
+    /// Minify the `source` code of a `kind` element and write the minified code to `out`. The message of the returned error is reported in `HTMLMinifierError::EmbeddedCodeError`.
+    fn minify(
+        &self,
+        kind: EmbeddedCodeKind,
+        source: &str,
+        out: &mut String,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+/// Minify JS code by [minifier](https://crates.io/crates/minifier).
+#[derive(Debug, Copy, Clone, Default)]
+pub struct JavaScriptMinifier;
+
+impl EmbeddedMinifier for JavaScriptMinifier {
+    #[inline]
+    fn minify(
+        &self,
+        _kind: EmbeddedCodeKind,
+        source: &str,
+        out: &mut String,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        write!(out, "{}", js::minify(source)).unwrap();
+
+        Ok(())
+    }
+}
+
+/// Minify CSS code by [minifier](https://crates.io/crates/minifier).
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CSSMinifier;
+
+impl EmbeddedMinifier for CSSMinifier {
+    #[inline]
+    fn minify(
+        &self,
+        _kind: EmbeddedCodeKind,
+        source: &str,
+        out: &mut String,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let minified_css = css::minify(source)?;
+
+        write!(out, "{minified_css}").unwrap();
+
+        Ok(())
+    }
+}
+
+/// Minify JSON data by removing the whitespaces outside strings. Invalid JSON data is kept as it is.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct JSONMinifier;
+
+impl EmbeddedMinifier for JSONMinifier {
+    #[inline]
+    fn minify(
+        &self,
+        _kind: EmbeddedCodeKind,
+        source: &str,
+        out: &mut String,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match minify_json(source) {
+            Some(minified_json) => out.push_str(&minified_json),
+            None => out.push_str(source),
+        }
+
+        Ok(())
+    }
+}
+
+/// Remove the whitespaces outside strings from JSON data. Return `None` if the data is not valid JSON.
+fn minify_json(json: &str) -> Option<String> {
+    let bytes = json.as_bytes();
+
+    let mut out = String::with_capacity(json.len());
+
+    // the closing brackets of the unclosed objects and arrays
+    let mut containers: Vec<u8> = Vec::new();
+
+    let mut p = 0;
+
+    loop {
+        // a value is expected
+        p = skip_json_whitespaces(bytes, p);
+
+        match *bytes.get(p)? {
+            c @ (b'{' | b'[') => {
+                let close = if c == b'{' { b'}' } else { b']' };
+
+                out.push(c as char);
+                p = skip_json_whitespaces(bytes, p + 1);
+
+                if bytes.get(p) == Some(&close) {
+                    out.push(close as char);
+                    p += 1;
+                } else {
+                    containers.push(close);
+
+                    if close == b'}' {
+                        p = push_json_key(json, p, &mut out)?;
+                    }
+
+                    continue;
+                }
+            },
+            b'"' => p = push_json_string(json, p, &mut out)?,
+            b'-' | b'0'..=b'9' => p = push_json_number(json, p, &mut out)?,
+            _ => {
+                let literal = ["true", "false", "null"]
+                    .into_iter()
+                    .find(|literal| bytes[p..].starts_with(literal.as_bytes()))?;
+
+                out.push_str(literal);
+                p += literal.len();
+            },
+        }
+
+        // a value has ended
+        loop {
+            p = skip_json_whitespaces(bytes, p);
+
+            let Some(&close) = containers.last() else {
+                return if p == bytes.len() { Some(out) } else { None };
+            };
+
+            match *bytes.get(p)? {
+                b',' => {
+                    out.push(',');
+                    p += 1;
+
+                    if close == b'}' {
+                        p = push_json_key(json, skip_json_whitespaces(bytes, p), &mut out)?;
+                    }
+
+                    break;
+                },
+                c if c == close => {
+                    out.push(c as char);
+                    p += 1;
+
+                    containers.pop();
+                },
+                _ => return None,
+            }
+        }
+    }
+}
+
+#[inline]
+fn skip_json_whitespaces(bytes: &[u8], mut p: usize) -> usize {
+    while let Some(b' ' | b'\t' | b'\n' | b'\r') = bytes.get(p) {
+        p += 1;
+    }
+
+    p
+}
+
+/// Push an object key at `p` and the colon following it. Return the index after the colon.
+#[inline]
+fn push_json_key(json: &str, p: usize, out: &mut String) -> Option<usize> {
+    let p = skip_json_whitespaces(json.as_bytes(), push_json_string(json, p, out)?);
+
+    if json.as_bytes().get(p) != Some(&b':') {
+        return None;
+    }
+
+    out.push(':');
+
+    Some(p + 1)
+}
+
+/// Push a string at `p`. Return the index after the string.
+fn push_json_string(json: &str, start: usize, out: &mut String) -> Option<usize> {
+    let bytes = json.as_bytes();
+
+    if bytes.get(start) != Some(&b'"') {
+        return None;
+    }
+
+    let mut p = start + 1;
+
+    loop {
+        match *bytes.get(p)? {
+            b'"' => break,
+            b'\\' => match *bytes.get(p + 1)? {
+                b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => p += 2,
+                b'u' => {
+                    if !bytes.get(p + 2..p + 6)?.iter().all(u8::is_ascii_hexdigit) {
+                        return None;
+                    }
+
+                    p += 6;
+                },
+                _ => return None,
+            },
+            0x00..=0x1F => return None,
+            _ => p += 1,
+        }
+    }
+
+    out.push_str(&json[start..=p]);
+
+    Some(p + 1)
+}
+
+/// Push a number at `p`. Return the index after the number.
+fn push_json_number(json: &str, start: usize, out: &mut String) -> Option<usize> {
+    let bytes = json.as_bytes();
+
+    let skip_digits = |mut p: usize| {
+        while bytes.get(p).is_some_and(u8::is_ascii_digit) {
+            p += 1;
+        }
+
+        p
+    };
+
+    let mut p = start;
+
+    if bytes[p] == b'-' {
+        p += 1;
+    }
+
+    match *bytes.get(p)? {
+        b'0' => p += 1,
+        b'1'..=b'9' => p = skip_digits(p + 1),
+        _ => return None,
+    }
+
+    if bytes.get(p) == Some(&b'.') {
+        if !bytes.get(p + 1)?.is_ascii_digit() {
+            return None;
+        }
+
+        p = skip_digits(p + 1);
+    }
+
+    if let Some(b'e' | b'E') = bytes.get(p) {
+        p += 1;
+
+        if let Some(b'+' | b'-') = bytes.get(p) {
+            p += 1;
+        }
+
+        if !bytes.get(p)?.is_ascii_digit() {
+            return None;
+        }
+
+        p = skip_digits(p);
+    }
+
+    out.push_str(&json[start..p]);
+
+    Some(p)
+}
@@ -4,11 +4,65 @@ use std::{
     io,
 };
 
+use educe::Educe;
+
+use crate::Diagnostic;
+
+/// A position in the input text digested by an html minifier.
+#[derive(Educe, Debug, Copy, Clone, Eq, PartialEq)]
+#[educe(Default)]
+pub struct Position {
+    /// The byte offset, starting from `0`.
+    pub offset: usize,
+    /// The line number, starting from `1`.
+    #[educe(Default = 1)]
+    pub line:   usize,
+    /// The column number in characters, starting from `1`.
+    #[educe(Default = 1)]
+    pub column: usize,
+}
+
+impl Position {
+    /// Move this position forward over the bytes.
+    #[inline]
+    pub(crate) fn advance(&mut self, bytes: &[u8]) {
+        for &e in bytes {
+            if e == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if e & 0xC0 != 0x80 {
+                // not a UTF-8 continuation byte
+                self.column += 1;
+            }
+        }
+
+        self.offset += bytes.len();
+    }
+}
+
+impl Display for Position {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_fmt(format_args!("line {}, column {}", self.line, self.column))
+    }
+}
+
 /// Errors for `HTMLMinifier`.
 #[derive(Debug)]
 pub enum HTMLMinifierError {
     CSSError(&'static str),
     IOError(io::Error),
+    /// The code embedded in a `script` or `style` element cannot be minified.
+    EmbeddedCodeError {
+        /// The position of the start tag of the element.
+        position: Position,
+        /// The tag name of the element.
+        tag:      String,
+        /// The message of the underlying error.
+        message:  String,
+    },
+    /// A comment, tag or element is not terminated at the end of the input. It is only returned by `finish` if the `collect_diagnostics` option is disabled.
+    Unterminated(Diagnostic),
 }
 
 impl From<io::Error> for HTMLMinifierError {
@@ -24,6 +78,14 @@ impl Display for HTMLMinifierError {
         match self {
             HTMLMinifierError::CSSError(error) => Display::fmt(error, f),
             HTMLMinifierError::IOError(error) => Display::fmt(error, f),
+            HTMLMinifierError::EmbeddedCodeError {
+                position,
+                tag,
+                message,
+            } => f.write_fmt(format_args!(
+                "failed to minify the content of the `{tag}` element at {position}: {message}"
+            )),
+            HTMLMinifierError::Unterminated(diagnostic) => Display::fmt(diagnostic, f),
         }
     }
 }
@@ -3,6 +3,8 @@ use std::{
     str::from_utf8_unchecked,
 };
 
+use crate::AttributeNormalization;
+
 #[inline]
 pub(crate) fn str_bytes_fmt(v: &[u8], f: &mut Formatter) -> Result<(), fmt::Error> {
     f.write_fmt(format_args!("{:?}", unsafe { from_utf8_unchecked(v) }))
@@ -17,3 +19,590 @@ pub(crate) const fn is_whitespace(e: u8) -> bool {
 pub(crate) const fn is_ascii_control(e: u8) -> bool {
     matches!(e, 0..=8 | 11..=31 | 127)
 }
+
+#[inline]
+pub(crate) const fn is_raw_text_element(tag: &[u8]) -> bool {
+    matches!(
+        tag,
+        b"script"
+            | b"style"
+            | b"textarea"
+            | b"title"
+            | b"xmp"
+            | b"iframe"
+            | b"noembed"
+            | b"noframes"
+            | b"noscript"
+            | b"plaintext"
+    )
+}
+
+/// Whitespaces around these elements are never rendered.
+#[inline]
+pub(crate) const fn is_block_element(tag: &[u8]) -> bool {
+    matches!(
+        tag,
+        b"html"
+            | b"head"
+            | b"body"
+            | b"title"
+            | b"base"
+            | b"link"
+            | b"meta"
+            | b"address"
+            | b"article"
+            | b"aside"
+            | b"blockquote"
+            | b"br"
+            | b"details"
+            | b"dialog"
+            | b"dd"
+            | b"div"
+            | b"dl"
+            | b"dt"
+            | b"fieldset"
+            | b"figcaption"
+            | b"figure"
+            | b"footer"
+            | b"form"
+            | b"h1"
+            | b"h2"
+            | b"h3"
+            | b"h4"
+            | b"h5"
+            | b"h6"
+            | b"header"
+            | b"hgroup"
+            | b"hr"
+            | b"legend"
+            | b"li"
+            | b"main"
+            | b"menu"
+            | b"nav"
+            | b"ol"
+            | b"optgroup"
+            | b"option"
+            | b"p"
+            | b"pre"
+            | b"section"
+            | b"summary"
+            | b"table"
+            | b"caption"
+            | b"colgroup"
+            | b"col"
+            | b"thead"
+            | b"tbody"
+            | b"tfoot"
+            | b"tr"
+            | b"td"
+            | b"th"
+            | b"ul"
+    )
+}
+
+/// Refer to https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+#[inline]
+pub(crate) const fn is_void_element(tag: &[u8]) -> bool {
+    matches!(
+        tag,
+        b"area"
+            | b"base"
+            | b"br"
+            | b"col"
+            | b"embed"
+            | b"hr"
+            | b"img"
+            | b"input"
+            | b"link"
+            | b"meta"
+            | b"source"
+            | b"track"
+            | b"wbr"
+            | b"param"
+            | b"keygen"
+    )
+}
+
+/// Whether the content of the element can be preserved until its end tag by attributes. Void elements have no end tags, and elements whose end tags can be omitted may be closed by other tags.
+#[inline]
+pub(crate) const fn can_preserve_content(tag: &[u8]) -> bool {
+    !is_void_element(tag) && !is_optional_end_tag(tag)
+}
+
+/// End tags of these elements can be omitted in some conditions.
+#[inline]
+pub(crate) const fn is_optional_end_tag(tag: &[u8]) -> bool {
+    matches!(
+        tag,
+        b"html"
+            | b"head"
+            | b"body"
+            | b"li"
+            | b"dt"
+            | b"dd"
+            | b"p"
+            | b"rt"
+            | b"rp"
+            | b"optgroup"
+            | b"option"
+            | b"colgroup"
+            | b"caption"
+            | b"thead"
+            | b"tbody"
+            | b"tfoot"
+            | b"tr"
+            | b"td"
+            | b"th"
+    )
+}
+
+/// Whether the end tag of the `tag` element can be omitted if it is followed by the `next` start tag (or end tag if `next_is_end_tag` is `true`) with (or without) a whitespace between them.
+///
+/// Refer to https://html.spec.whatwg.org/multipage/syntax.html#optional-tags
+pub(crate) fn can_omit_end_tag(
+    tag: &[u8],
+    has_whitespace: bool,
+    next_is_end_tag: bool,
+    next: &[u8],
+) -> bool {
+    match tag {
+        b"html" | b"body" => true,
+        b"head" | b"colgroup" | b"caption" => !has_whitespace,
+        _ if has_whitespace => false,
+        _ if next_is_end_tag => match tag {
+            b"dt" | b"thead" => false,
+            // autonomous custom elements have a hyphen in their names
+            b"p" => {
+                !(matches!(
+                    next,
+                    b"a" | b"audio" | b"del" | b"ins" | b"map" | b"noscript" | b"video"
+                ) || next.contains(&b'-'))
+            },
+            _ => true,
+        },
+        b"li" => next == b"li",
+        b"dt" | b"dd" => matches!(next, b"dt" | b"dd"),
+        b"rt" | b"rp" => matches!(next, b"rt" | b"rp"),
+        b"optgroup" => matches!(next, b"optgroup" | b"hr"),
+        b"option" => matches!(next, b"option" | b"optgroup" | b"hr"),
+        b"thead" | b"tbody" => matches!(next, b"tbody" | b"tfoot"),
+        b"tr" => next == b"tr",
+        b"td" | b"th" => matches!(next, b"td" | b"th"),
+        b"p" => matches!(
+            next,
+            b"address"
+                | b"article"
+                | b"aside"
+                | b"blockquote"
+                | b"details"
+                | b"dialog"
+                | b"div"
+                | b"dl"
+                | b"fieldset"
+                | b"figcaption"
+                | b"figure"
+                | b"footer"
+                | b"form"
+                | b"h1"
+                | b"h2"
+                | b"h3"
+                | b"h4"
+                | b"h5"
+                | b"h6"
+                | b"header"
+                | b"hgroup"
+                | b"hr"
+                | b"main"
+                | b"menu"
+                | b"nav"
+                | b"ol"
+                | b"p"
+                | b"pre"
+                | b"search"
+                | b"section"
+                | b"table"
+                | b"ul"
+        ),
+        _ => false,
+    }
+}
+
+#[inline]
+pub(crate) const fn is_optional_start_tag(tag: &[u8]) -> bool {
+    matches!(tag, b"html" | b"head" | b"body" | b"colgroup" | b"tbody")
+}
+
+/// Whether the start tag (without attributes) of the `tag` element can be omitted if it is followed by the `next` start tag (or end tag if `next_is_end_tag` is `true`) with (or without) a whitespace between them.
+///
+/// Refer to https://html.spec.whatwg.org/multipage/syntax.html#optional-tags
+pub(crate) fn can_omit_start_tag(
+    tag: &[u8],
+    has_whitespace: bool,
+    next_is_end_tag: bool,
+    next: &[u8],
+) -> bool {
+    match tag {
+        b"html" => true,
+        _ if has_whitespace => false,
+        b"head" => !next_is_end_tag || next == b"head",
+        b"body" if next_is_end_tag => next == b"body",
+        b"body" => {
+            !matches!(next, b"meta" | b"noscript" | b"link" | b"script" | b"style" | b"template")
+        },
+        b"colgroup" => !next_is_end_tag && next == b"col",
+        b"tbody" => !next_is_end_tag && next == b"tr",
+        _ => false,
+    }
+}
+
+/// Whether the attribute value can be written without quotes.
+#[inline]
+pub(crate) fn can_unquote_attribute_value(value: &[u8]) -> bool {
+    !value.is_empty()
+        && !value
+            .iter()
+            .any(|&e| is_whitespace(e) || matches!(e, b'"' | b'\'' | b'=' | b'<' | b'>' | b'`'))
+}
+
+/// Match a quote or a character reference to a quote at the beginning of the text. Return the quote (or `0` if not matched) and the length of the matched bytes.
+pub(crate) fn match_quote(text: &[u8]) -> (u8, usize) {
+    match text[0] {
+        b'"' | b'\'' => (text[0], 1),
+        b'&' => {
+            // named references are case-sensitive (`&APOS;` is not a reference)
+            const REFERENCES: [(&[u8], u8); 9] = [
+                (b"&quot;", b'"'),
+                (b"&QUOT;", b'"'),
+                (b"&#34;", b'"'),
+                (b"&#x22;", b'"'),
+                (b"&#X22;", b'"'),
+                (b"&apos;", b'\''),
+                (b"&#39;", b'\''),
+                (b"&#x27;", b'\''),
+                (b"&#X27;", b'\''),
+            ];
+
+            for (reference, quote) in REFERENCES {
+                if text.starts_with(reference) {
+                    return (quote, reference.len());
+                }
+            }
+
+            (0, 1)
+        },
+        _ => (0, 1),
+    }
+}
+
+/// Refer to https://html.spec.whatwg.org/multipage/indices.html#attributes-3
+#[inline]
+pub(crate) const fn is_boolean_attribute(name: &[u8]) -> bool {
+    matches!(
+        name,
+        b"allowfullscreen"
+            | b"async"
+            | b"autofocus"
+            | b"autoplay"
+            | b"checked"
+            | b"controls"
+            | b"default"
+            | b"defer"
+            | b"disabled"
+            | b"formnovalidate"
+            | b"hidden"
+            | b"inert"
+            | b"ismap"
+            | b"itemscope"
+            | b"loop"
+            | b"multiple"
+            | b"muted"
+            | b"nomodule"
+            | b"novalidate"
+            | b"open"
+            | b"playsinline"
+            | b"readonly"
+            | b"required"
+            | b"reversed"
+            | b"selected"
+            | b"shadowrootclonable"
+            | b"shadowrootdelegatesfocus"
+            | b"shadowrootserializable"
+    )
+}
+
+/// Whether the attribute value (lowercased and decoded) equals the default of the `tag` element.
+///
+/// Refer to https://html.spec.whatwg.org/multipage/indices.html#attributes-3
+pub(crate) fn is_default_attribute_value(tag: &[u8], name: &[u8], value: &str) -> bool {
+    match (tag, name) {
+        (b"script", b"type") => matches!(value, "text/javascript" | "application/javascript"),
+        (b"style", b"type") => value == "text/css",
+        (b"style" | b"link", b"media") => value == "all",
+        (b"form", b"method") => value == "get",
+        (b"form", b"enctype") => value == "application/x-www-form-urlencoded",
+        (b"form", b"autocomplete") => value == "on",
+        (b"input", b"type") => value == "text",
+        (b"button", b"type") => value == "submit",
+        (b"td" | b"th", b"colspan" | b"rowspan") => value == "1",
+        (b"col" | b"colgroup", b"span") => value == "1",
+        (b"area", b"shape") => value == "rect",
+        (b"textarea", b"wrap") => value == "soft",
+        (b"track", b"kind") => value == "subtitles",
+        (b"img", b"decoding") => value == "auto",
+        (b"img" | b"iframe", b"loading") => value == "eager",
+        (b"img" | b"link" | b"script" | b"iframe", b"fetchpriority") => value == "auto",
+        _ => false,
+    }
+}
+
+/// Normalize the attribute value in place.
+pub(crate) fn normalize_attribute_value(
+    normalization: AttributeNormalization,
+    value: &mut Vec<u8>,
+) {
+    match normalization {
+        AttributeNormalization::TokenList | AttributeNormalization::CommaSeparatedList => {
+            let comma_separated = normalization == AttributeNormalization::CommaSeparatedList;
+
+            let mut length = 0;
+            let mut spacing = false;
+
+            for i in 0..value.len() {
+                let e = value[i];
+
+                if is_whitespace(e) {
+                    spacing = length > 0;
+                } else {
+                    // whitespaces before commas are useless, but the ones after commas are kept because they may separate URLs in `srcset`
+                    if spacing && !(comma_separated && e == b',') {
+                        value[length] = b' ';
+                        length += 1;
+                    }
+
+                    value[length] = e;
+                    length += 1;
+
+                    spacing = false;
+                }
+            }
+
+            value.truncate(length);
+        },
+        AttributeNormalization::TrimmedURL | AttributeNormalization::CaseInsensitiveKeyword => {
+            let end = value.iter().rposition(|e| !is_whitespace(*e)).map_or(0, |i| i + 1);
+            value.truncate(end);
+
+            let start = value.iter().position(|e| !is_whitespace(*e)).unwrap_or(end);
+            value.drain(..start);
+
+            if normalization == AttributeNormalization::CaseInsensitiveKeyword {
+                value.make_ascii_lowercase();
+            }
+        },
+    }
+}
+
+/// Whether the attribute (lowercased) is an event handler such as `onclick`. Only the `on` prefix followed by a known event name matches, so that attributes such as `only` are not treated as JS code.
+///
+/// Refer to https://html.spec.whatwg.org/multipage/webappapis.html#globaleventhandlers
+#[inline]
+pub(crate) fn is_event_handler_attribute(name: &[u8]) -> bool {
+    let Some(event) = name.strip_prefix(b"on") else {
+        return false;
+    };
+
+    matches!(
+        event,
+        b"abort"
+            | b"afterprint"
+            | b"animationcancel"
+            | b"animationend"
+            | b"animationiteration"
+            | b"animationstart"
+            | b"auxclick"
+            | b"beforecopy"
+            | b"beforecut"
+            | b"beforeinput"
+            | b"beforematch"
+            | b"beforepaste"
+            | b"beforeprint"
+            | b"beforetoggle"
+            | b"beforeunload"
+            | b"blur"
+            | b"cancel"
+            | b"canplay"
+            | b"canplaythrough"
+            | b"change"
+            | b"click"
+            | b"close"
+            | b"contextlost"
+            | b"contextmenu"
+            | b"contextrestored"
+            | b"copy"
+            | b"cuechange"
+            | b"cut"
+            | b"dblclick"
+            | b"drag"
+            | b"dragend"
+            | b"dragenter"
+            | b"dragleave"
+            | b"dragover"
+            | b"dragstart"
+            | b"drop"
+            | b"durationchange"
+            | b"emptied"
+            | b"ended"
+            | b"error"
+            | b"focus"
+            | b"focusin"
+            | b"focusout"
+            | b"formdata"
+            | b"fullscreenchange"
+            | b"fullscreenerror"
+            | b"gotpointercapture"
+            | b"hashchange"
+            | b"input"
+            | b"invalid"
+            | b"keydown"
+            | b"keypress"
+            | b"keyup"
+            | b"languagechange"
+            | b"load"
+            | b"loadeddata"
+            | b"loadedmetadata"
+            | b"loadend"
+            | b"loadstart"
+            | b"lostpointercapture"
+            | b"message"
+            | b"messageerror"
+            | b"mousedown"
+            | b"mouseenter"
+            | b"mouseleave"
+            | b"mousemove"
+            | b"mouseout"
+            | b"mouseover"
+            | b"mouseup"
+            | b"offline"
+            | b"online"
+            | b"pagehide"
+            | b"pagereveal"
+            | b"pageshow"
+            | b"pageswap"
+            | b"paste"
+            | b"pause"
+            | b"play"
+            | b"playing"
+            | b"pointercancel"
+            | b"pointerdown"
+            | b"pointerenter"
+            | b"pointerleave"
+            | b"pointermove"
+            | b"pointerout"
+            | b"pointerover"
+            | b"pointerrawupdate"
+            | b"pointerup"
+            | b"popstate"
+            | b"progress"
+            | b"ratechange"
+            | b"rejectionhandled"
+            | b"reset"
+            | b"resize"
+            | b"scroll"
+            | b"scrollend"
+            | b"search"
+            | b"securitypolicyviolation"
+            | b"seeked"
+            | b"seeking"
+            | b"select"
+            | b"selectionchange"
+            | b"selectstart"
+            | b"slotchange"
+            | b"stalled"
+            | b"storage"
+            | b"submit"
+            | b"suspend"
+            | b"timeupdate"
+            | b"toggle"
+            | b"touchcancel"
+            | b"touchend"
+            | b"touchmove"
+            | b"touchstart"
+            | b"transitioncancel"
+            | b"transitionend"
+            | b"transitionrun"
+            | b"transitionstart"
+            | b"unhandledrejection"
+            | b"unload"
+            | b"visibilitychange"
+            | b"volumechange"
+            | b"waiting"
+            | b"wheel"
+    )
+}
+
+/// Whether the MIME type essence (lowercased) is a JavaScript MIME type.
+///
+/// Refer to https://mimesniff.spec.whatwg.org/#javascript-mime-type
+#[inline]
+pub(crate) const fn is_javascript_mime_type(essence: &[u8]) -> bool {
+    matches!(
+        essence,
+        b"application/ecmascript"
+            | b"application/javascript"
+            | b"application/x-ecmascript"
+            | b"application/x-javascript"
+            | b"text/ecmascript"
+            | b"text/javascript"
+            | b"text/javascript1.0"
+            | b"text/javascript1.1"
+            | b"text/javascript1.2"
+            | b"text/javascript1.3"
+            | b"text/javascript1.4"
+            | b"text/javascript1.5"
+            | b"text/jscript"
+            | b"text/livescript"
+            | b"text/x-ecmascript"
+            | b"text/x-javascript"
+    )
+}
+
+/// Get the essence of a MIME type by removing its parameters, e.g. `text/javascript; charset=utf-8` to `text/javascript`.
+#[inline]
+pub(crate) fn mime_type_essence(mime_type: &str) -> &str {
+    let essence = match mime_type.find(';') {
+        Some(index) => &mime_type[..index],
+        None => mime_type,
+    };
+
+    essence.trim_matches(|c: char| c.is_ascii() && is_whitespace(c as u8))
+}
+
+/// Whether the MIME type essence (lowercased) is a JSON type of `script` elements.
+#[inline]
+pub(crate) const fn is_json_mime_type(essence: &[u8]) -> bool {
+    matches!(
+        essence,
+        b"application/json" | b"application/ld+json" | b"importmap" | b"speculationrules"
+    )
+}
+
+/// Whether the comment (without `<!--` and `-->`) is an IE conditional comment, e.g. `[if IE]>...<![endif]` or `<![endif]`.
+#[inline]
+pub(crate) fn is_conditional_comment(comment: &[u8]) -> bool {
+    comment.starts_with(b"[if ") || comment.starts_with(b"<![endif]")
+}
+
+/// Split a conditional comment (without `<!--` and `-->`) into the condition (e.g. `[if IE]>`), the inner HTML and `<![endif]`.
+#[inline]
+pub(crate) fn split_conditional_comment(comment: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    const END: &[u8] = b"<![endif]";
+
+    let head_length = comment.windows(2).position(|w| w == b"]>")? + 2;
+    let tail_index = comment.len().checked_sub(END.len())?;
+
+    if head_length > tail_index || &comment[tail_index..] != END {
+        return None;
+    }
+
+    Some((&comment[..head_length], &comment[head_length..tail_index], &comment[tail_index..]))
+}
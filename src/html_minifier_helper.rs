@@ -1,10 +1,14 @@
-use std::{borrow::Cow, str::from_utf8_unchecked};
+use std::{str::from_utf8_unchecked, sync::Arc};
 
 use educe::Educe;
 pub use minifier::{css, js};
 use str_utils::ToLowercase;
 
-use crate::{HTMLMinifierError, HTMLWriter, functions::*};
+use crate::{
+    AttributeNormalization, CSSMinifier, Diagnostic, DiagnosticKind, EmbeddedCodeKind,
+    EmbeddedErrorPolicy, EmbeddedMinifier, HTMLMinifierError, HTMLMinifierOptions, HTMLWriter,
+    JSONMinifier, JavaScriptMinifier, Position, WhitespaceCollapse, functions::*,
+};
 
 #[derive(Educe, Debug, Copy, Clone, Eq, PartialEq)]
 #[educe(Default)]
@@ -26,86 +30,293 @@ enum Step {
     TagEnd,
     Doctype,
     Comment,
-    ScriptDefault,
-    ScriptJavaScript,
-    StyleDefault,
-    StyleCSS,
-    Pre,
-    Code,
-    Textarea,
+    EmbeddedCode,
+    Raw,
 }
 
+/// The code embedded in an attribute value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum AttributeCode {
+    /// CSS declarations in the `style` attribute.
+    Style,
+    /// JS code in an event handler attribute such as `onclick`.
+    EventHandler,
+    /// A URL which may use the `javascript:` scheme.
+    JavaScriptURL,
+}
+
+/// An attribute collected until the end of its start tag.
+#[derive(Educe, Clone)]
+#[educe(Debug)]
+struct Attribute {
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    name:           Vec<u8>,
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    name_lowercase: Vec<u8>,
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    value:          Vec<u8>,
+    /// The original quote of the value, or `0` if the value is unquoted.
+    quote:          u8,
+    has_value:      bool,
+}
+
+type CommentFilter = dyn Fn(&str) -> bool + Send + Sync;
+
 /// This struct helps you generate and minify your HTML code in the same time. The output destination is outside this struct.
 #[derive(Educe, Clone)]
 #[educe(Debug, Default(new))]
 pub struct HTMLMinifierHelper {
-    #[educe(Default = true)]
-    /// Remove HTML comments.
-    pub remove_comments: bool,
-    #[educe(Default = true)]
-    /// Minify the content in the `code` element.
-    pub minify_code:     bool,
+    pub(crate) options: HTMLMinifierOptions,
+    #[educe(Debug(ignore))]
+    embedded_minifiers: Vec<(String, Arc<dyn EmbeddedMinifier>)>,
+    #[educe(Debug(ignore))]
+    comment_filter:     Option<Arc<CommentFilter>>,
+    warnings:           Vec<String>,
+    diagnostics:        Vec<Diagnostic>,
 
     // Buffers
     #[educe(Debug(method = "str_bytes_fmt"))]
-    buffer:         Vec<u8>,
+    buffer:             Vec<u8>,
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    tag:                Vec<u8>,
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    tag_raw:            Vec<u8>,
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    pending_tag:        Vec<u8>,
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    pending_tag_name:   Vec<u8>,
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    attribute_type:     Vec<u8>,
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    attribute_language: Vec<u8>,
     #[educe(Debug(method = "str_bytes_fmt"))]
-    tag:            Vec<u8>,
+    attribute_value:    Vec<u8>,
     #[educe(Debug(method = "str_bytes_fmt"))]
-    attribute_type: Vec<u8>,
+    attribute_name:     Vec<u8>,
+    /// The names of the attributes in the current start tag, separated by spaces. Used only for diagnostics.
+    #[educe(Debug(method = "str_bytes_fmt"))]
+    attribute_names:    Vec<u8>,
+    attributes:         Vec<Attribute>,
 
     // Steps
     step:         Step,
-    step_counter: u8,
+    step_counter: usize,
+
+    // Positions
+    /// The position of `text[scanned]` in the text being digested.
+    position:           Position,
+    scanned:            usize,
+    tag_position:       Position,
+    attribute_position: Position,
 
     // Temp
-    quote:      u8,
-    last_space: u8,
+    quote:       u8,
+    last_space:  u8,
+    line_breaks: usize,
+
+    // Attributes
+    attribute_count: usize,
+
+    // Raw elements
+    #[educe(Debug(ignore))]
+    embedded_minifier: Option<(EmbeddedCodeKind, Arc<dyn EmbeddedMinifier>)>,
+    raw_depth:         usize,
+    raw_end_tag:       bool,
 
     // Flags
-    quoted_value_spacing: bool,
-    quoted_value_empty:   bool,
-    in_handled_attribute: bool,
-    in_attribute_type:    bool,
+    holding_tag:             bool,
+    in_end_tag:              bool,
+    raw_marked:              bool,
+    raw_nestable:            bool,
+    quoted_value_spacing:    bool,
+    quoted_value_empty:      bool,
+    in_handled_attribute:    bool,
+    attribute_normalization: Option<AttributeNormalization>,
+    attribute_code:          Option<AttributeCode>,
+    in_attribute_type:       bool,
+    in_attribute_language:   bool,
+    has_attribute_type:      bool,
+    capture_attribute_value: bool,
+    omitting_tag:            bool,
+    pending_start_tag:       bool,
+    after_table_row:         bool,
+    in_boolean_attribute:    bool,
+    buffering_value:         bool,
 }
 
 impl HTMLMinifierHelper {
     #[inline]
     fn set_flags_by_attribute(&mut self) {
-        match self.buffer.as_slice() {
-            b"class" => {
-                self.in_handled_attribute = true;
-                self.in_attribute_type = false;
-            },
-            b"type" => match self.tag.as_slice() {
-                b"script" | b"style" => {
-                    self.in_handled_attribute = true;
-                    self.in_attribute_type = true;
-                },
-                _ => (),
+        let name = self.buffer.as_slice();
+
+        let normalization = match name {
+            b"type" if matches!(self.tag.as_slice(), b"script" | b"style") => {
+                self.in_attribute_type = true;
+
+                Some(AttributeNormalization::TokenList)
             },
             _ => {
-                self.in_handled_attribute = false;
                 self.in_attribute_type = false;
+
+                self.options
+                    .attribute_normalizations
+                    .iter()
+                    .find(|(attribute, _)| attribute.as_bytes() == name)
+                    .map(|(_, normalization)| *normalization)
             },
-        }
+        };
+
+        self.in_attribute_language = name == b"language" && self.tag == b"script";
+
+        // whitespaces in token lists are collapsed while the value is being written
+        self.in_handled_attribute = matches!(
+            normalization,
+            Some(AttributeNormalization::TokenList | AttributeNormalization::CommaSeparatedList)
+        );
+        self.attribute_code = match name {
+            b"style" if self.options.minify_style_attributes => Some(AttributeCode::Style),
+            _ if self.options.minify_event_handlers && is_event_handler_attribute(name) => {
+                Some(AttributeCode::EventHandler)
+            },
+            b"href" | b"src" | b"action" | b"formaction" if self.options.minify_javascript_urls => {
+                Some(AttributeCode::JavaScriptURL)
+            },
+            _ => None,
+        };
+        self.buffering_value = self.defers_attributes()
+            || self.attribute_code.is_some()
+            || matches!(
+                normalization,
+                Some(
+                    AttributeNormalization::CommaSeparatedList
+                        | AttributeNormalization::TrimmedURL
+                        | AttributeNormalization::CaseInsensitiveKeyword
+                )
+            );
+        self.attribute_normalization = normalization;
+
+        self.in_boolean_attribute = self.options.collapse_boolean_attributes
+            && !self.tag.contains(&b'-')
+            && is_boolean_attribute(name);
+
+        self.capture_attribute_value =
+            self.in_attribute_type
+                || self.in_attribute_language
+                || (!self.raw_marked
+                    && self.options.whitespace_preserving_rules.iter().any(|rule| {
+                        rule.get_attribute_name().as_bytes().eq_ignore_ascii_case(name)
+                    }));
+
+        self.attribute_value.clear();
     }
 
     #[inline]
     fn finish_buffer(&mut self) {
+        if self.buffering_value
+            && let Some(normalization) = self.attribute_normalization
+        {
+            normalize_attribute_value(normalization, &mut self.attribute_value);
+        }
+
+        if self.buffering_value
+            && let Some(code) = self.attribute_code
+        {
+            self.minify_attribute_code(code);
+        }
+
+        if !self.capture_attribute_value {
+            return;
+        }
+
+        let attribute_value = html_escape::decode_html_entities(unsafe {
+            from_utf8_unchecked(&self.attribute_value)
+        });
+
         if self.in_attribute_type {
-            if let Cow::Owned(attribute_value) = html_escape::decode_html_entities(unsafe {
-                from_utf8_unchecked(&self.attribute_type)
-            }) {
-                self.attribute_type = attribute_value.into_bytes();
+            self.attribute_type =
+                mime_type_essence(&attribute_value.to_ascii_lowercase_cow()).as_bytes().to_vec();
+        } else if self.in_attribute_language {
+            self.attribute_language = attribute_value
+                .trim_matches(|c: char| c.is_ascii() && is_whitespace(c as u8))
+                .to_ascii_lowercase()
+                .into_bytes();
+        } else {
+            let name = self.buffer.as_slice();
+
+            self.raw_marked = self
+                .options
+                .whitespace_preserving_rules
+                .iter()
+                .any(|rule| rule.matches(name, &attribute_value));
+        }
+    }
+
+    /// Minify the code in the buffered attribute value. The value is kept if the code cannot be minified.
+    fn minify_attribute_code(&mut self, code: AttributeCode) {
+        let value = html_escape::decode_html_entities(unsafe {
+            from_utf8_unchecked(&self.attribute_value)
+        });
+
+        let minified = match code {
+            AttributeCode::Style => minify_style_declarations(&value),
+            AttributeCode::EventHandler => Some(js::minify(&value).to_string()),
+            AttributeCode::JavaScriptURL => minify_javascript_url(&value),
+        };
+
+        if let Some(minified) = minified {
+            self.attribute_value.clear();
+
+            // quotes are escaped when the value is written
+            for e in minified.bytes() {
+                if e == b'&' {
+                    self.attribute_value.extend_from_slice(b"&amp;");
+                } else {
+                    self.attribute_value.push(e);
+                }
             }
+        }
+    }
 
-            if let Cow::Owned(attribute_value) =
-                unsafe { from_utf8_unchecked(&self.attribute_type) }.to_ascii_lowercase_cow()
-            {
-                self.attribute_type = attribute_value.into_bytes();
+    #[inline]
+    fn finish_attribute_name(&mut self, text_bytes: &[u8], p: usize) {
+        if self.options.collect_diagnostics {
+            // attribute names never contain whitespaces, so the start can be located from the end
+            let mut position = self.locate(text_bytes, p);
+            position.offset -= self.buffer.len();
+            position.column -= self.buffer.iter().filter(|&&e| e & 0xC0 != 0x80).count();
+
+            self.attribute_position = position;
+
+            let name = self.buffer.as_slice();
+
+            if self.attribute_names.split(|&e| e == b' ').any(|n| n == name) {
+                self.diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::DuplicateAttribute,
+                    position,
+                    message: format!("duplicate attribute `{}`", String::from_utf8_lossy(name)),
+                });
+            } else {
+                self.attribute_names.push(b' ');
+                self.attribute_names.extend_from_slice(name);
             }
         }
+
+        // a `type` attribute without a value still makes the `language` attribute ignored
+        if self.buffer == b"type" && matches!(self.tag.as_slice(), b"script" | b"style") {
+            self.has_attribute_type = true;
+        }
+
+        if !self.raw_marked {
+            let name = self.buffer.as_slice();
+
+            self.raw_marked =
+                self.options.raw_attributes.iter().any(|attribute| attribute.as_bytes() == name)
+                    || self
+                        .options
+                        .whitespace_preserving_rules
+                        .iter()
+                        .any(|rule| rule.matches(name, ""));
+        }
     }
 
     #[inline]
@@ -116,73 +327,860 @@ impl HTMLMinifierHelper {
         start: &mut usize,
         p: usize,
     ) -> Result<Step, HTMLMinifierError> {
+        // the `language` attribute is used only if the `type` attribute is absent
+        if self.tag == b"script" && !self.has_attribute_type && !self.attribute_language.is_empty()
+        {
+            self.attribute_type.extend_from_slice(b"text/");
+            self.attribute_type.extend_from_slice(&self.attribute_language);
+        }
+
+        self.has_attribute_type = false;
+        self.attribute_language.clear();
+
         let step = match self.tag.as_slice() {
-            b"script" => {
-                self.step_counter = 0;
+            b"script" | b"style" => {
+                let kind = if self.tag == b"script" {
+                    EmbeddedCodeKind::Script
+                } else {
+                    EmbeddedCodeKind::Style
+                };
 
-                match self.attribute_type.as_slice() {
-                    b"" | b"application/javascript" | b"module" => {
-                        out.push_bytes(&text_bytes[*start..=p])?;
-                        *start = p + 1;
+                self.embedded_minifier =
+                    self.find_embedded_minifier(kind).map(|minifier| (kind, minifier));
 
-                        self.attribute_type.clear();
-                        self.buffer.clear();
+                self.attribute_type.clear();
+                self.start_raw(false);
 
-                        Step::ScriptJavaScript
-                    },
-                    _ => {
-                        self.attribute_type.clear();
+                if self.embedded_minifier.is_some() {
+                    out.push_bytes(&text_bytes[*start..=p])?;
+                    *start = p + 1;
 
-                        Step::ScriptDefault
-                    },
+                    self.buffer.clear();
+
+                    Step::EmbeddedCode
+                } else {
+                    Step::Raw
                 }
             },
-            b"style" => {
-                self.step_counter = 0;
+            tag if (self.raw_marked && can_preserve_content(tag))
+                || (tag == b"code" && !self.options.minify_code)
+                || self.options.raw_elements.iter().any(|element| element.as_bytes() == tag) =>
+            {
+                self.start_raw(!is_raw_text_element(tag));
+                Step::Raw
+            },
+            _ => self.get_step_after_tag(),
+        };
 
-                match self.attribute_type.as_slice() {
-                    b"" | b"text/css" => {
-                        out.push_bytes(&text_bytes[*start..=p])?;
-                        *start = p + 1;
+        Ok(step)
+    }
 
-                        self.attribute_type.clear();
-                        self.buffer.clear();
+    /// Find the minifier for the content of the current `script` or `style` element by its type. The minifiers added by `add_embedded_minifier` take precedence over the built-in ones.
+    fn find_embedded_minifier(&self, kind: EmbeddedCodeKind) -> Option<Arc<dyn EmbeddedMinifier>> {
+        if self.raw_marked {
+            return None;
+        }
 
-                        Step::StyleCSS
-                    },
-                    _ => {
-                        self.attribute_type.clear();
+        let mime_type = match (kind, self.attribute_type.as_slice()) {
+            (EmbeddedCodeKind::Script, b"") => b"text/javascript".as_slice(),
+            (EmbeddedCodeKind::Style, b"") => b"text/css".as_slice(),
+            (_, mime_type) => mime_type,
+        };
+
+        if let Some((_, minifier)) =
+            self.embedded_minifiers.iter().find(|(t, _)| t.as_bytes() == mime_type)
+        {
+            return Some(minifier.clone());
+        }
+
+        let minifier: Arc<dyn EmbeddedMinifier> = match kind {
+            EmbeddedCodeKind::Script
+                if mime_type == b"module"
+                    || is_javascript_mime_type(mime_type)
+                    || self.options.javascript_types.iter().any(|t| t.as_bytes() == mime_type) =>
+            {
+                Arc::new(JavaScriptMinifier)
+            },
+            EmbeddedCodeKind::Script if is_json_mime_type(mime_type) => Arc::new(JSONMinifier),
+            EmbeddedCodeKind::Style if mime_type == b"text/css" => Arc::new(CSSMinifier),
+            _ => return None,
+        };
+
+        Some(minifier)
+    }
+
+    #[inline]
+    fn get_step_after_tag(&mut self) -> Step {
+        self.last_space = 0;
+
+        // rows outside an explicit table section are in an implicit `tbody`, which only a `<tbody>` start tag can close
+        match self.tag.as_slice() {
+            b"tr" => self.after_table_row = true,
+            b"table" if !self.in_end_tag => self.after_table_row = false,
+            b"tbody" | b"thead" | b"tfoot" | b"caption" | b"colgroup" => {
+                self.after_table_row = false
+            },
+            _ => (),
+        }
+
+        if self.options.aggressive_whitespace && is_block_element(&self.tag) {
+            Step::Initial
+        } else {
+            Step::InitialRemainOneWhitespace
+        }
+    }
+
+    #[inline]
+    fn write_whitespace(&self, out: &mut impl HTMLWriter) -> Result<(), HTMLMinifierError> {
+        if self.last_space == b'\n' {
+            match self.options.whitespace_collapse {
+                WhitespaceCollapse::Newline => out.push(b'\n')?,
+                WhitespaceCollapse::Space => out.push(b' ')?,
+                WhitespaceCollapse::PreserveLineBreaks => {
+                    for _ in 0..self.line_breaks {
+                        out.push(b'\n')?;
+                    }
+                },
+            }
+        } else if self.last_space > 0 {
+            out.push(b' ')?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the whitespace before a tag is pending until the tag name is known.
+    #[inline]
+    fn holds_whitespace(&self) -> bool {
+        self.options.aggressive_whitespace
+            || self.options.remove_optional_end_tags
+            || self.options.remove_optional_start_tags
+    }
+
+    #[inline]
+    fn write_pending_tag(&mut self, out: &mut impl HTMLWriter) -> Result<(), HTMLMinifierError> {
+        out.push_bytes(if self.pending_start_tag { b"<" } else { b"</" })?;
+        out.push_bytes(&self.pending_tag)?;
+        out.push(b'>')?;
+
+        self.pending_tag.clear();
+
+        Ok(())
+    }
+
+    /// Write the pending tag and the pending whitespace.
+    #[inline]
+    fn write_pending(&mut self, out: &mut impl HTMLWriter) -> Result<(), HTMLMinifierError> {
+        if !self.pending_tag.is_empty() {
+            self.write_pending_tag(out)?;
+        }
+
+        self.write_whitespace(out)
+    }
+
+    /// Write the held start tag which cannot be omitted because it has attributes or is self-closing.
+    #[inline]
+    fn write_omitted_start_tag(
+        &mut self,
+        out: &mut impl HTMLWriter,
+    ) -> Result<(), HTMLMinifierError> {
+        out.push(b'<')?;
+        out.push_bytes(&self.pending_tag)?;
 
-                        Step::StyleDefault
+        self.pending_tag.clear();
+        self.omitting_tag = false;
+
+        Ok(())
+    }
+
+    /// Write the held `<` or `</` and the tag name. The pending whitespace is written before them unless it is useless around a block element. The pending tag is written before the whitespace unless it can be omitted.
+    ///
+    /// If `is_tag` is `false`, the held bytes turn out to be text.
+    #[inline]
+    fn write_held_tag(
+        &mut self,
+        out: &mut impl HTMLWriter,
+        name: &[u8],
+        is_tag: bool,
+    ) -> Result<(), HTMLMinifierError> {
+        let keep_whitespace = self.last_space > 0
+            && !(is_tag && self.options.aggressive_whitespace && is_block_element(&self.tag));
+
+        // whether the element is immediately preceded by an element whose end tag is omitted
+        let mut after_omitted_end_tag = false;
+
+        if !self.pending_tag.is_empty() {
+            let omit = is_tag
+                && if self.pending_start_tag {
+                    can_omit_start_tag(
+                        &self.pending_tag_name,
+                        keep_whitespace,
+                        self.in_end_tag,
+                        &self.tag,
+                    )
+                } else {
+                    can_omit_end_tag(
+                        &self.pending_tag_name,
+                        keep_whitespace,
+                        self.in_end_tag,
+                        &self.tag,
+                    )
+                };
+
+            if omit {
+                after_omitted_end_tag = !self.pending_start_tag;
+
+                self.pending_tag.clear();
+            } else {
+                self.write_pending_tag(out)?;
+            }
+        }
+
+        if keep_whitespace {
+            self.write_whitespace(out)?;
+        }
+
+        self.last_space = 0;
+
+        if is_tag
+            && self.in_end_tag
+            && self.options.remove_optional_end_tags
+            && is_optional_end_tag(&self.tag)
+        {
+            // the end tag is pending until the next tag is known
+            self.pending_tag.extend_from_slice(&self.tag_raw);
+            self.pending_tag.extend_from_slice(name);
+            self.pending_tag_name.clone_from(&self.tag);
+
+            self.pending_start_tag = false;
+            self.omitting_tag = true;
+        } else if is_tag
+            && !self.in_end_tag
+            && self.options.remove_optional_start_tags
+            && is_optional_start_tag(&self.tag)
+            && !(after_omitted_end_tag
+                && match self.tag.as_slice() {
+                    b"colgroup" => self.pending_tag_name == b"colgroup",
+                    b"tbody" => {
+                        matches!(self.pending_tag_name.as_slice(), b"tbody" | b"thead" | b"tfoot")
                     },
+                    _ => false,
+                })
+            && !(self.after_table_row && self.tag == b"tbody")
+        {
+            // the start tag is pending until it is known to have no attributes and the next tag is known
+            self.pending_tag.extend_from_slice(&self.tag_raw);
+            self.pending_tag.extend_from_slice(name);
+            self.pending_tag_name.clone_from(&self.tag);
+
+            self.pending_start_tag = true;
+            self.omitting_tag = true;
+        } else {
+            out.push_bytes(if self.in_end_tag { b"</" } else { b"<" })?;
+            out.push_bytes(&self.tag_raw)?;
+            out.push_bytes(name)?;
+        }
+
+        self.holding_tag = false;
+
+        Ok(())
+    }
+
+    /// Whether attributes are collected until the end of the start tag so that they can be rewritten or removed before being written.
+    #[inline]
+    fn defers_attributes(&self) -> bool {
+        self.options.remove_attribute_quotes
+            || self.options.collapse_boolean_attributes
+            || self.options.remove_redundant_attributes
+            || self.options.remove_empty_attributes
+            || self.options.deduplicate_class_names
+            || self.options.sort_class_names
+            || self.options.sort_attributes
+    }
+
+    #[inline]
+    fn start_attribute(&mut self, e: u8) {
+        self.buffer.clear();
+        self.buffer.push(e.to_ascii_lowercase());
+
+        self.attribute_name.clear();
+        self.attribute_name.push(e);
+    }
+
+    /// Collect the attribute whose name is in `self.attribute_name` and whose value is in `self.attribute_value`.
+    #[inline]
+    fn finish_attribute(&mut self, has_value: bool, quote: u8) {
+        let mut has_value = has_value && !self.attribute_value.is_empty();
+        let mut quote = quote;
+
+        if self.options.remove_empty_attributes {
+            let name = self.buffer.as_slice();
+
+            // whitespaces alone have no effect in these attributes
+            let is_blank =
+                matches!(name, b"class" | b"style" | b"id") || is_event_handler_attribute(name);
+
+            if is_blank && self.attribute_value.iter().all(|e| is_whitespace(*e)) {
+                has_value = false;
+            }
+        }
+
+        if !has_value && self.options.remove_empty_attributes {
+            let name = self.buffer.as_slice();
+
+            if self.options.empty_attributes.iter().any(|attribute| {
+                match attribute.strip_suffix('*') {
+                    Some(prefix) => name.starts_with(prefix.as_bytes()),
+                    None => attribute.as_bytes() == name,
+                }
+            }) {
+                return;
+            }
+        }
+
+        if has_value
+            && self.buffer == b"class"
+            && (self.options.deduplicate_class_names || self.options.sort_class_names)
+        {
+            let mut class_names: Vec<&[u8]> = self
+                .attribute_value
+                .split(|e| is_whitespace(*e))
+                .filter(|class_name| !class_name.is_empty())
+                .collect();
+
+            if self.options.sort_class_names {
+                class_names.sort();
+            }
+
+            if self.options.deduplicate_class_names {
+                let mut unique_class_names = Vec::with_capacity(class_names.len());
+
+                for class_name in class_names {
+                    if !unique_class_names.contains(&class_name) {
+                        unique_class_names.push(class_name);
+                    }
                 }
+
+                class_names = unique_class_names;
+            }
+
+            self.attribute_value = class_names.join(&b' ');
+        }
+
+        if has_value && self.in_boolean_attribute {
+            // the value of a boolean attribute is ignored except for `hidden="until-found"`
+            if self.buffer == b"hidden" && self.attribute_value.eq_ignore_ascii_case(b"until-found")
+            {
+                self.attribute_value.make_ascii_lowercase();
+
+                quote = 0;
+            } else {
+                has_value = false;
+            }
+        }
+
+        if self.attribute_count == self.attributes.len() {
+            self.attributes.push(Attribute {
+                name: Vec::new(),
+                name_lowercase: Vec::new(),
+                value: Vec::new(),
+                quote,
+                has_value,
+            });
+        }
+
+        let attribute = &mut self.attributes[self.attribute_count];
+
+        attribute.name.clone_from(&self.attribute_name);
+        attribute.name_lowercase.clone_from(&self.buffer);
+        attribute.value.clear();
+
+        if has_value {
+            attribute.value.extend_from_slice(&self.attribute_value);
+        }
+
+        attribute.quote = quote;
+        attribute.has_value = has_value;
+
+        self.attribute_count += 1;
+    }
+
+    /// Write the buffered attribute value (with `=`) of an attribute which is not deferred.
+    #[inline]
+    fn write_buffered_attribute_value(
+        &mut self,
+        out: &mut impl HTMLWriter,
+        quote: u8,
+    ) -> Result<(), HTMLMinifierError> {
+        if !self.attribute_value.is_empty() {
+            out.push(b'=')?;
+
+            write_attribute_value(out, &self.attribute_value, quote, false)?;
+        }
+
+        self.last_space = 0;
+
+        Ok(())
+    }
+
+    /// Whether the collected attribute equals the default of the element.
+    fn is_redundant_attribute(&self, attribute: &Attribute) -> bool {
+        if !attribute.has_value {
+            return false;
+        }
+
+        let value =
+            html_escape::decode_html_entities(unsafe { from_utf8_unchecked(&attribute.value) });
+        let value = value.to_ascii_lowercase_cow();
+
+        match (self.tag.as_slice(), attribute.name_lowercase.as_slice()) {
+            (b"link", b"type") => {
+                value == "text/css"
+                    && self.attributes[..self.attribute_count].iter().any(|attribute| {
+                        attribute.name_lowercase == b"rel"
+                            && html_escape::decode_html_entities(unsafe {
+                                from_utf8_unchecked(&attribute.value)
+                            })
+                            .split_ascii_whitespace()
+                            .any(|token| token.eq_ignore_ascii_case("stylesheet"))
+                    })
             },
-            b"pre" => {
-                self.step_counter = 0;
-                Step::Pre
+            // without the `type` attribute, the `language` attribute would decide the script type
+            (b"script", b"type") => {
+                is_default_attribute_value(b"script", b"type", &value)
+                    && !self.attributes[..self.attribute_count]
+                        .iter()
+                        .any(|attribute| attribute.name_lowercase == b"language")
             },
-            b"code" => {
-                if self.minify_code {
-                    self.last_space = 0;
+            (tag, name) => is_default_attribute_value(tag, name, &value),
+        }
+    }
 
-                    Step::InitialRemainOneWhitespace
-                } else {
-                    self.step_counter = 0;
-                    Step::Code
+    /// Write the collected attributes when the start tag ends with `e` (`/` or `>`).
+    fn write_attributes(
+        &mut self,
+        out: &mut impl HTMLWriter,
+        e: u8,
+        start: &mut usize,
+        p: usize,
+    ) -> Result<(), HTMLMinifierError> {
+        debug_assert_eq!(*start, p);
+
+        if self.options.sort_attributes {
+            // the sort is stable so the first one of duplicate attributes is still effective
+            self.attributes[..self.attribute_count]
+                .sort_by(|a, b| a.name_lowercase.cmp(&b.name_lowercase));
+        }
+
+        let mut unquoted = false;
+
+        for index in 0..self.attribute_count {
+            if self.options.remove_redundant_attributes
+                && self.is_redundant_attribute(&self.attributes[index])
+            {
+                continue;
+            }
+
+            if self.omitting_tag {
+                self.write_omitted_start_tag(out)?;
+            }
+
+            let attribute = &self.attributes[index];
+
+            out.push(b' ')?;
+            out.push_bytes(&attribute.name)?;
+
+            unquoted = false;
+
+            if attribute.has_value {
+                out.push(b'=')?;
+
+                unquoted = write_attribute_value(
+                    out,
+                    &attribute.value,
+                    attribute.quote,
+                    self.options.remove_attribute_quotes,
+                )?;
+            }
+        }
+
+        self.attribute_count = 0;
+
+        if e == b'/' {
+            if self.omitting_tag {
+                self.write_omitted_start_tag(out)?;
+            }
+
+            // `<a b=c />` cannot be written as `<a b=c/>`
+            if unquoted {
+                out.push(b' ')?;
+            }
+        } else if self.omitting_tag {
+            *start = p + 1;
+        }
+
+        Ok(())
+    }
+
+    /// Write the collected attributes and the buffered attribute value of a start tag which is not terminated at the end of the input, without rewriting them.
+    fn write_unterminated_attributes(
+        &mut self,
+        out: &mut impl HTMLWriter,
+    ) -> Result<(), HTMLMinifierError> {
+        let defers_attributes = self.defers_attributes();
+
+        if defers_attributes {
+            for attribute in &self.attributes[..self.attribute_count] {
+                out.push(b' ')?;
+                out.push_bytes(&attribute.name)?;
+
+                if attribute.has_value {
+                    out.push(b'=')?;
+
+                    if attribute.quote > 0 {
+                        out.push(attribute.quote)?;
+                        out.push_bytes(&attribute.value)?;
+                        out.push(attribute.quote)?;
+                    } else {
+                        out.push_bytes(&attribute.value)?;
+                    }
                 }
+            }
+
+            self.attribute_count = 0;
+
+            // the name of the current attribute is collected as well
+            if matches!(
+                self.step,
+                Step::StartTagAttributeName
+                    | Step::StartTagAttributeNameWaitingValue
+                    | Step::StartTagAttributeValueInitial
+                    | Step::StartTagUnquotedAttributeValue
+                    | Step::StartTagQuotedAttributeValue
+            ) {
+                out.push(b' ')?;
+                out.push_bytes(&self.attribute_name)?;
+            }
+        }
+
+        match self.step {
+            Step::StartTagAttributeValueInitial => out.push(b'=')?,
+            Step::StartTagUnquotedAttributeValue if self.buffering_value => {
+                out.push(b'=')?;
+                out.push_bytes(&self.attribute_value)?;
             },
-            b"textarea" => {
-                self.step_counter = 0;
-                Step::Textarea
+            Step::StartTagQuotedAttributeValue
+                if self.buffering_value || self.quoted_value_empty =>
+            {
+                out.push_bytes(&[b'=', self.quote])?;
+
+                if self.buffering_value {
+                    out.push_bytes(&self.attribute_value)?;
+                }
+            },
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn start_raw(&mut self, nestable: bool) {
+        self.step_counter = 0;
+        self.raw_depth = 0;
+        self.raw_nestable = nestable;
+    }
+
+    /// Write the code in `self.buffer[..length]` minified by the embedded minifier of the current element, followed by the rest of the buffer.
+    #[inline]
+    fn write_minified_code(
+        &mut self,
+        out: &mut impl HTMLWriter,
+        length: usize,
+    ) -> Result<(), HTMLMinifierError> {
+        if let Some((kind, minifier)) = self.embedded_minifier.take() {
+            let mut minified_code = String::with_capacity(length);
+
+            match minifier.minify(
+                kind,
+                unsafe { from_utf8_unchecked(&self.buffer[..length]) },
+                &mut minified_code,
+            ) {
+                Ok(()) => {
+                    out.push_bytes(minified_code.as_bytes())?;
+                    out.push_bytes(&self.buffer[length..])?;
+                },
+                Err(error) => {
+                    let error = HTMLMinifierError::EmbeddedCodeError {
+                        position: self.tag_position,
+                        tag:      String::from_utf8_lossy(&self.tag).into_owned(),
+                        message:  error.to_string(),
+                    };
+
+                    match self.options.embedded_error_policy {
+                        EmbeddedErrorPolicy::Abort => return Err(error),
+                        EmbeddedErrorPolicy::Verbatim => out.push_bytes(&self.buffer)?,
+                        EmbeddedErrorPolicy::Warn => {
+                            self.warnings.push(error.to_string());
+
+                            out.push_bytes(&self.buffer)?;
+                        },
+                    }
+                },
+            }
+        } else {
+            out.push_bytes(&self.buffer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether the removed comments are buffered so that some of them can be kept.
+    #[inline]
+    fn filters_comments(&self) -> bool {
+        self.options.keep_conditional_comments
+            || !self.options.preserved_comment_prefixes.is_empty()
+            || self.comment_filter.is_some()
+    }
+
+    /// Whether comments are buffered in `self.buffer` so that they can be filtered or minified by `write_filtered_comment`.
+    #[inline]
+    fn buffers_comments(&self) -> bool {
+        if self.options.remove_comments {
+            self.filters_comments()
+        } else {
+            self.options.minify_conditional_comments
+        }
+    }
+
+    /// Write the comment in `self.buffer` (followed by `-->`) if it should be kept.
+    fn write_filtered_comment(
+        &mut self,
+        out: &mut impl HTMLWriter,
+    ) -> Result<(), HTMLMinifierError> {
+        let comment = &self.buffer[..self.buffer.len() - 3];
+
+        if (self.options.keep_conditional_comments || !self.options.remove_comments)
+            && is_conditional_comment(comment)
+        {
+            if self.options.minify_conditional_comments
+                && let Some((head, inner, tail)) = split_conditional_comment(comment)
+            {
+                let mut helper = HTMLMinifierHelper::with_options(self.options.clone());
+                helper.embedded_minifiers.clone_from(&self.embedded_minifiers);
+                helper.comment_filter.clone_from(&self.comment_filter);
+
+                // the nested helper locates its input from where it is in this comment
+                helper.position = self.tag_position;
+                helper.position.advance(b"<!--");
+                helper.position.advance(head);
+
+                let mut minified_html = Vec::with_capacity(inner.len());
+
+                helper.digest(inner, &mut minified_html)?;
+
+                match helper.finish(&mut minified_html) {
+                    Ok(()) | Err(HTMLMinifierError::Unterminated(_)) => (),
+                    Err(error) => return Err(error),
+                }
+
+                out.push_bytes(b"<!--")?;
+                out.push_bytes(head)?;
+                out.push_bytes(&minified_html)?;
+                out.push_bytes(tail)?;
+                out.push_bytes(b"-->")?;
+
+                self.diagnostics.append(&mut helper.diagnostics);
+                self.warnings.append(&mut helper.warnings);
+
+                return Ok(());
+            }
+        } else if self.options.remove_comments {
+            let trimmed_comment = comment.trim_ascii_start();
+
+            let keep = self
+                .options
+                .preserved_comment_prefixes
+                .iter()
+                .any(|prefix| trimmed_comment.starts_with(prefix.as_bytes()))
+                || self
+                    .comment_filter
+                    .as_ref()
+                    .is_some_and(|filter| filter(&String::from_utf8_lossy(comment)));
+
+            if !keep {
+                return Ok(());
+            }
+        }
+
+        out.push_bytes(b"<!--")?;
+        out.push_bytes(&self.buffer)?;
+
+        Ok(())
+    }
+
+    /// Record a diagnostic if the `collect_diagnostics` option is enabled.
+    #[inline]
+    fn report(
+        &mut self,
+        kind: DiagnosticKind,
+        position: Position,
+        message: impl FnOnce(&Self) -> String,
+    ) {
+        if self.options.collect_diagnostics {
+            let message = message(self);
+
+            self.diagnostics.push(Diagnostic {
+                kind,
+                position,
+                message,
+            });
+        }
+    }
+
+    /// Get the position of `text_bytes[p]`. `p` must not be less than the one passed last time during the same `digest`.
+    #[inline]
+    fn locate(&mut self, text_bytes: &[u8], p: usize) -> Position {
+        self.position.advance(&text_bytes[self.scanned..p]);
+        self.scanned = p;
+
+        self.position
+    }
+
+    /// Match `</tag` (the tag name is in `self.tag`) byte by byte. Return `true` if it is matched and `e` is the byte following it.
+    ///
+    /// If the raw element is nestable, the nested `<tag` start tags are counted so that only the matching end tag is returned.
+    #[inline]
+    fn match_end_tag(&mut self, e: u8) -> bool {
+        match self.step_counter {
+            0 => {
+                if e == b'<' {
+                    self.step_counter = 1;
+                }
+            },
+            1 => {
+                if e == b'/' {
+                    self.raw_end_tag = true;
+                    self.step_counter = 2;
+                } else if self.raw_nestable && self.tag.first() == Some(&e.to_ascii_lowercase()) {
+                    self.raw_end_tag = false;
+                    self.step_counter = 3;
+                } else if e != b'<' {
+                    self.step_counter = 0;
+                }
             },
             _ => {
-                self.last_space = 0;
+                let index = self.step_counter - 2;
 
-                Step::InitialRemainOneWhitespace
+                if index < self.tag.len() {
+                    if self.tag[index] == e.to_ascii_lowercase() {
+                        self.step_counter += 1;
+                    } else {
+                        self.step_counter = if e == b'<' { 1 } else { 0 };
+                    }
+                } else if self.raw_end_tag {
+                    self.step_counter = 0;
+
+                    if e == b'>' || is_whitespace(e) {
+                        if self.raw_depth == 0 {
+                            return true;
+                        }
+
+                        self.raw_depth -= 1;
+                    } else if e == b'<' {
+                        self.step_counter = 1;
+                    }
+                } else {
+                    self.step_counter = 0;
+
+                    if e == b'>' || e == b'/' || is_whitespace(e) {
+                        self.raw_depth += 1;
+                    } else if e == b'<' {
+                        self.step_counter = 1;
+                    }
+                }
             },
-        };
+        }
 
-        Ok(step)
+        false
+    }
+}
+
+impl HTMLMinifierHelper {
+    /// Create a new `HTMLMinifierHelper` instance with the given options.
+    #[inline]
+    pub fn with_options(options: HTMLMinifierOptions) -> Self {
+        Self {
+            options,
+            ..Self::new()
+        }
+    }
+
+    /// Get the options of this html minifier helper.
+    #[inline]
+    pub const fn get_options(&self) -> &HTMLMinifierOptions {
+        &self.options
+    }
+
+    /// Set the options of this html minifier helper.
+    #[inline]
+    pub fn set_options(&mut self, options: HTMLMinifierOptions) {
+        self.options = options;
+    }
+
+    /// Set whether to remove HTML comments.
+    #[inline]
+    pub fn set_remove_comments(&mut self, remove_comments: bool) {
+        self.options.remove_comments = remove_comments;
+    }
+
+    /// Set whether to minify the content in the `code` element.
+    #[inline]
+    pub fn set_minify_code(&mut self, minify_code: bool) {
+        self.options.minify_code = minify_code;
+    }
+
+    /// Get whether to remove HTML comments.
+    #[inline]
+    pub const fn get_remove_comments(&self) -> bool {
+        self.options.remove_comments
+    }
+
+    /// Get whether to minify the content in the `code` element.
+    #[inline]
+    pub const fn get_minify_code(&self) -> bool {
+        self.options.minify_code
+    }
+
+    /// Add a minifier for the content of `script` and `style` elements of the MIME type, e.g. `text/babel`. The MIME type is case-insensitive and its parameters are ignored. The elements without the `type` attribute are of `text/javascript` and `text/css`. A minifier added for the same MIME type is replaced.
+    #[inline]
+    pub fn add_embedded_minifier<S: AsRef<str>>(
+        &mut self,
+        mime_type: S,
+        minifier: Arc<dyn EmbeddedMinifier>,
+    ) {
+        let mime_type = mime_type_essence(&mime_type.as_ref().to_ascii_lowercase()).to_string();
+
+        self.embedded_minifiers.retain(|(t, _)| *t != mime_type);
+        self.embedded_minifiers.push((mime_type, minifier));
+    }
+
+    /// Set a function to decide whether to keep a comment (without `<!--` and `-->`) when comments are removed. It is used after the `preserved_comment_prefixes` option.
+    #[inline]
+    pub fn set_comment_filter<F: Fn(&str) -> bool + Send + Sync + 'static>(&mut self, filter: F) {
+        self.comment_filter = Some(Arc::new(filter));
+    }
+
+    /// Get the warnings recorded with the `EmbeddedErrorPolicy::Warn` policy since the last reset.
+    #[inline]
+    pub fn get_warnings(&self) -> &[String] {
+        self.warnings.as_slice()
+    }
+
+    /// Get the diagnostics collected with the `collect_diagnostics` option since the last reset.
+    #[inline]
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        self.diagnostics.as_slice()
     }
 }
 
@@ -191,8 +1189,18 @@ impl HTMLMinifierHelper {
     #[inline]
     pub fn reset(&mut self) {
         self.step = Step::default();
-
+        self.last_space = 0;
+        self.holding_tag = false;
+        self.pending_tag.clear();
+
+        self.embedded_minifier = None;
+        self.warnings.clear();
+        self.diagnostics.clear();
+        self.position = Position::default();
         self.attribute_type.clear();
+        self.attribute_language.clear();
+        self.has_attribute_type = false;
+        self.attribute_count = 0;
     }
 
     /// Input some text to generate HTML code. It is not necessary to input a full HTML text at once.
@@ -207,6 +1215,8 @@ impl HTMLMinifierHelper {
         let mut start = 0;
         let mut p = 0;
 
+        self.scanned = 0;
+
         while p < text_length {
             let e = text_bytes[p];
 
@@ -224,13 +1234,17 @@ impl HTMLMinifierHelper {
                                     out.push_bytes(&text_bytes[start..p])?;
                                     start = p + 1;
 
+                                    self.tag_position = self.locate(text_bytes, p);
                                     self.step = Step::StartTagInitial;
                                 },
                                 _ => {
                                     if is_whitespace(e) {
-                                        debug_assert_eq!(start, p);
+                                        // the end of the previous block element may not be written yet
+                                        out.push_bytes(&text_bytes[start..p])?;
                                         start = p + 1;
                                     } else {
+                                        self.write_pending(out)?;
+
                                         self.last_space = 0;
                                         self.step = Step::InitialRemainOneWhitespace;
                                     }
@@ -244,14 +1258,20 @@ impl HTMLMinifierHelper {
                                 start = p + 1;
 
                                 self.last_space = e;
+                                self.line_breaks = usize::from(e == b'\n');
 
                                 self.step = Step::InitialIgnoreWhitespace;
                             } else if e == b'<' {
                                 out.push_bytes(&text_bytes[start..p])?;
                                 start = p + 1;
 
+                                self.tag_position = self.locate(text_bytes, p);
                                 self.step = Step::StartTagInitial;
                             } else {
+                                if !self.pending_tag.is_empty() {
+                                    self.write_pending(out)?;
+                                }
+
                                 self.last_space = 0;
                             }
                         },
@@ -264,6 +1284,7 @@ impl HTMLMinifierHelper {
 
                                     if self.last_space > 0 {
                                         self.last_space = b'\n';
+                                        self.line_breaks += 1;
                                     }
                                 },
                                 0x09 | 0x0B..=0x0D | 0x1C..=0x20 => {
@@ -271,24 +1292,19 @@ impl HTMLMinifierHelper {
                                     start = p + 1;
                                 },
                                 b'<' => {
-                                    // This can just push ' ', but the minified HTML would be ugly
-                                    if self.last_space == b'\n' {
-                                        out.push(b'\n')?;
-                                    } else if self.last_space > 0 {
-                                        out.push(b' ')?;
+                                    // The whitespace may be useless, so it is pending until the tag name is known
+                                    if !self.holds_whitespace() {
+                                        self.write_whitespace(out)?;
                                     }
 
                                     out.push_bytes(&text_bytes[start..p])?;
                                     start = p + 1;
 
+                                    self.tag_position = self.locate(text_bytes, p);
                                     self.step = Step::StartTagInitial;
                                 },
                                 _ => {
-                                    if self.last_space == b'\n' {
-                                        out.push(b'\n')?;
-                                    } else if self.last_space > 0 {
-                                        out.push(b' ')?;
-                                    }
+                                    self.write_pending(out)?;
 
                                     self.last_space = 0;
                                     self.step = Step::InitialRemainOneWhitespace;
@@ -309,6 +1325,10 @@ impl HTMLMinifierHelper {
                                     // <!
                                     start = p + 1;
 
+                                    if self.holds_whitespace() {
+                                        self.write_pending(out)?;
+                                    }
+
                                     self.step_counter = 0;
                                     self.step = Step::Doctype;
                                 },
@@ -316,23 +1336,46 @@ impl HTMLMinifierHelper {
                                     // <>
                                     start = p + 1;
 
+                                    if self.holds_whitespace() {
+                                        self.write_pending(out)?;
+                                    }
+
                                     self.last_space = 0;
                                     self.step = Step::InitialRemainOneWhitespace;
                                 },
                                 _ => {
-                                    out.push(b'<')?;
-
                                     if is_whitespace(e) {
-                                        out.push_bytes(&text_bytes[start..p])?;
+                                        if self.holds_whitespace() {
+                                            self.write_pending(out)?;
+                                        }
+
+                                        out.push(b'<')?;
+
                                         start = p + 1;
 
                                         self.last_space = e;
+                                        self.line_breaks = usize::from(e == b'\n');
 
                                         self.step = Step::InitialIgnoreWhitespace;
                                     } else {
+                                        if self.options.remove_optional_start_tags
+                                            || (self.holds_whitespace()
+                                                && (self.last_space > 0
+                                                    || !self.pending_tag.is_empty()))
+                                        {
+                                            self.tag_raw.clear();
+                                            self.holding_tag = true;
+                                        } else {
+                                            out.push(b'<')?;
+                                        }
+
                                         self.tag.clear();
                                         self.tag.push(e.to_ascii_lowercase());
+                                        self.attribute_names.clear();
 
+                                        self.in_end_tag = false;
+                                        self.omitting_tag = false;
+                                        self.raw_marked = false;
                                         self.step = Step::StartTag;
                                     }
                                 },
@@ -345,19 +1388,56 @@ impl HTMLMinifierHelper {
                                     // </>
                                     start = p + 1;
 
+                                    self.report(
+                                        DiagnosticKind::StrayEndTagOpen,
+                                        self.tag_position,
+                                        |_| String::from("`</` is not followed by a tag name"),
+                                    );
+
+                                    if self.holds_whitespace() {
+                                        self.write_pending(out)?;
+                                    }
+
                                     self.last_space = 0;
                                     self.step = Step::InitialRemainOneWhitespace;
                                 },
                                 _ => {
-                                    out.push_bytes(b"</")?;
-
                                     if is_whitespace(e) {
+                                        self.report(
+                                            DiagnosticKind::StrayEndTagOpen,
+                                            self.tag_position,
+                                            |_| String::from("`</` is not followed by a tag name"),
+                                        );
+
+                                        if self.holds_whitespace() {
+                                            self.write_pending(out)?;
+                                        }
+
+                                        out.push_bytes(b"</")?;
+
                                         start = p + 1;
 
                                         self.last_space = e;
+                                        self.line_breaks = usize::from(e == b'\n');
 
                                         self.step = Step::InitialIgnoreWhitespace;
                                     } else {
+                                        if self.options.remove_optional_end_tags
+                                            || (self.holds_whitespace()
+                                                && (self.last_space > 0
+                                                    || !self.pending_tag.is_empty()))
+                                        {
+                                            self.tag_raw.clear();
+                                            self.holding_tag = true;
+                                        } else {
+                                            out.push_bytes(b"</")?;
+                                        }
+
+                                        self.tag.clear();
+                                        self.tag.push(e.to_ascii_lowercase());
+
+                                        self.in_end_tag = true;
+                                        self.omitting_tag = false;
                                         self.step = Step::EndTag;
                                     }
                                 },
@@ -366,7 +1446,12 @@ impl HTMLMinifierHelper {
                         Step::StartTag => {
                             // <a?
                             if is_whitespace(e) {
-                                out.push_bytes(&text_bytes[start..p])?;
+                                if self.holding_tag {
+                                    self.write_held_tag(out, &text_bytes[start..p], true)?;
+                                } else {
+                                    out.push_bytes(&text_bytes[start..p])?;
+                                }
+
                                 start = p + 1;
 
                                 self.buffer.clear(); // the buffer may be used for the `type` attribute
@@ -375,8 +1460,24 @@ impl HTMLMinifierHelper {
                                 self.step = Step::StartTagIn;
                             } else {
                                 match e {
-                                    b'/' => self.step = Step::TagEnd,
+                                    b'/' => {
+                                        if self.holding_tag {
+                                            self.write_held_tag(out, &text_bytes[start..p], true)?;
+                                            start = p;
+
+                                            if self.omitting_tag {
+                                                self.write_omitted_start_tag(out)?;
+                                            }
+                                        }
+
+                                        self.step = Step::TagEnd;
+                                    },
                                     b'>' => {
+                                        if self.holding_tag {
+                                            self.write_held_tag(out, &text_bytes[start..p], true)?;
+                                            start = if self.omitting_tag { p + 1 } else { p };
+                                        }
+
                                         self.buffer.clear(); // the buffer may be used for the `type` attribute
 
                                         self.step = self.end_start_tag_and_get_next_step(
@@ -391,13 +1492,28 @@ impl HTMLMinifierHelper {
                             // <a ?
                             match e {
                                 b'/' => {
-                                    if self.last_space > 0 {
-                                        out.push(b' ')?;
+                                    if self.defers_attributes() {
+                                        self.write_attributes(out, e, &mut start, p)?;
+                                    } else {
+                                        if self.omitting_tag {
+                                            self.write_omitted_start_tag(out)?;
+                                        }
+
+                                        if self.last_space > 0 {
+                                            out.push(b' ')?;
+                                        }
                                     }
 
                                     self.step = Step::TagEnd;
                                 },
                                 b'>' => {
+                                    if self.defers_attributes() {
+                                        self.write_attributes(out, e, &mut start, p)?;
+                                    } else if self.omitting_tag {
+                                        debug_assert_eq!(start, p);
+                                        start = p + 1;
+                                    }
+
                                     self.step = self.end_start_tag_and_get_next_step(
                                         out, text_bytes, &mut start, p,
                                     )?;
@@ -406,7 +1522,18 @@ impl HTMLMinifierHelper {
                                     if is_whitespace(e) {
                                         debug_assert_eq!(start, p);
                                         start = p + 1;
+                                    } else if self.defers_attributes() {
+                                        debug_assert_eq!(start, p);
+                                        start = p + 1;
+
+                                        self.start_attribute(e);
+
+                                        self.step = Step::StartTagAttributeName;
                                     } else {
+                                        if self.omitting_tag {
+                                            self.write_omitted_start_tag(out)?;
+                                        }
+
                                         out.push(b' ')?;
 
                                         self.buffer.clear();
@@ -420,8 +1547,24 @@ impl HTMLMinifierHelper {
                         Step::StartTagAttributeName => {
                             // <a a?
                             match e {
-                                b'/' => self.step = Step::TagEnd,
+                                b'/' => {
+                                    self.finish_attribute_name(text_bytes, p);
+
+                                    if self.defers_attributes() {
+                                        self.finish_attribute(false, 0);
+                                        self.write_attributes(out, e, &mut start, p)?;
+                                    }
+
+                                    self.step = Step::TagEnd;
+                                },
                                 b'>' => {
+                                    self.finish_attribute_name(text_bytes, p);
+
+                                    if self.defers_attributes() {
+                                        self.finish_attribute(false, 0);
+                                        self.write_attributes(out, e, &mut start, p)?;
+                                    }
+
                                     self.step = self.end_start_tag_and_get_next_step(
                                         out, text_bytes, &mut start, p,
                                     )?;
@@ -430,6 +1573,7 @@ impl HTMLMinifierHelper {
                                     out.push_bytes(&text_bytes[start..p])?;
                                     start = p + 1;
 
+                                    self.finish_attribute_name(text_bytes, p);
                                     self.set_flags_by_attribute();
 
                                     self.step = Step::StartTagAttributeValueInitial;
@@ -439,9 +1583,18 @@ impl HTMLMinifierHelper {
                                         out.push_bytes(&text_bytes[start..p])?;
                                         start = p + 1;
 
+                                        self.finish_attribute_name(text_bytes, p);
+
                                         self.step = Step::StartTagAttributeNameWaitingValue;
                                     } else {
                                         self.buffer.push(e.to_ascii_lowercase());
+
+                                        if self.defers_attributes() {
+                                            debug_assert_eq!(start, p);
+                                            start = p + 1;
+
+                                            self.attribute_name.push(e);
+                                        }
                                     }
                                 },
                             }
@@ -449,8 +1602,20 @@ impl HTMLMinifierHelper {
                         Step::StartTagAttributeNameWaitingValue => {
                             // <a a ?
                             match e {
-                                b'/' => self.step = Step::TagEnd,
+                                b'/' => {
+                                    if self.defers_attributes() {
+                                        self.finish_attribute(false, 0);
+                                        self.write_attributes(out, e, &mut start, p)?;
+                                    }
+
+                                    self.step = Step::TagEnd;
+                                },
                                 b'>' => {
+                                    if self.defers_attributes() {
+                                        self.finish_attribute(false, 0);
+                                        self.write_attributes(out, e, &mut start, p)?;
+                                    }
+
                                     self.step = self.end_start_tag_and_get_next_step(
                                         out, text_bytes, &mut start, p,
                                     )?;
@@ -467,6 +1632,14 @@ impl HTMLMinifierHelper {
                                     if is_whitespace(e) {
                                         debug_assert_eq!(start, p);
                                         start = p + 1;
+                                    } else if self.defers_attributes() {
+                                        debug_assert_eq!(start, p);
+                                        start = p + 1;
+
+                                        self.finish_attribute(false, 0);
+                                        self.start_attribute(e);
+
+                                        self.step = Step::StartTagAttributeName;
                                     } else {
                                         out.push(b' ')?;
 
@@ -482,11 +1655,25 @@ impl HTMLMinifierHelper {
                             // <a a=?
                             debug_assert_eq!(start, p);
 
+                            // a `/` here is the first byte of an unquoted value, e.g. `<a href=/>`
                             match e {
-                                b'/' => {
-                                    self.step = Step::TagEnd;
-                                },
                                 b'>' => {
+                                    self.report(
+                                        DiagnosticKind::MissingAttributeValue,
+                                        self.attribute_position,
+                                        |h| {
+                                            format!(
+                                                "missing value of the attribute `{}`",
+                                                String::from_utf8_lossy(&h.buffer)
+                                            )
+                                        },
+                                    );
+
+                                    if self.defers_attributes() {
+                                        self.finish_attribute(false, 0);
+                                        self.write_attributes(out, e, &mut start, p)?;
+                                    }
+
                                     self.step = self.end_start_tag_and_get_next_step(
                                         out, text_bytes, &mut start, p,
                                     )?;
@@ -504,11 +1691,15 @@ impl HTMLMinifierHelper {
                                     if is_whitespace(e) {
                                         start = p + 1;
                                     } else {
-                                        if self.in_attribute_type {
-                                            self.attribute_type.push(e);
+                                        if self.capture_attribute_value || self.buffering_value {
+                                            self.attribute_value.push(e);
                                         }
 
-                                        out.push(b'=')?;
+                                        if self.buffering_value {
+                                            start = p + 1;
+                                        } else {
+                                            out.push(b'=')?;
+                                        }
 
                                         self.step = Step::StartTagUnquotedAttributeValue;
                                     }
@@ -519,7 +1710,32 @@ impl HTMLMinifierHelper {
                             // <a a="?
                             // <a a='?
                             // NOTE: Backslashes cannot be used for escaping.
-                            if e == self.quote {
+                            if self.buffering_value {
+                                debug_assert_eq!(start, p);
+                                start = p + 1;
+
+                                if e == self.quote {
+                                    self.finish_buffer();
+
+                                    if self.defers_attributes() {
+                                        self.finish_attribute(true, self.quote);
+                                    } else {
+                                        self.write_buffered_attribute_value(out, self.quote)?;
+                                    }
+
+                                    self.step = Step::StartTagIn;
+                                } else if self.in_handled_attribute && is_whitespace(e) {
+                                    self.quoted_value_spacing = !self.attribute_value.is_empty();
+                                } else {
+                                    if self.quoted_value_spacing {
+                                        self.attribute_value.push(b' ');
+                                    }
+
+                                    self.attribute_value.push(e);
+
+                                    self.quoted_value_spacing = false;
+                                }
+                            } else if e == self.quote {
                                 if self.quoted_value_empty {
                                     start = p + 1;
                                 }
@@ -556,12 +1772,12 @@ impl HTMLMinifierHelper {
                                     out.push(b' ')?;
                                 }
 
-                                if self.in_attribute_type {
+                                if self.capture_attribute_value {
                                     if self.quoted_value_spacing {
-                                        self.attribute_type.push(b' ');
+                                        self.attribute_value.push(b' ');
                                     }
 
-                                    self.attribute_type.push(e);
+                                    self.attribute_value.push(e);
                                 }
 
                                 self.quoted_value_spacing = false;
@@ -574,20 +1790,39 @@ impl HTMLMinifierHelper {
                                 b'>' => {
                                     self.finish_buffer();
 
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
+                                    if self.defers_attributes() {
+                                        self.finish_attribute(true, 0);
+                                        self.write_attributes(out, e, &mut start, p)?;
+                                    } else if self.buffering_value {
+                                        self.write_buffered_attribute_value(out, 0)?;
+                                    }
+
+                                    self.step = self.end_start_tag_and_get_next_step(
+                                        out, text_bytes, &mut start, p,
+                                    )?;
                                 },
                                 _ => {
                                     if is_whitespace(e) {
                                         self.finish_buffer();
 
+                                        if self.defers_attributes() {
+                                            self.finish_attribute(true, 0);
+                                        } else if self.buffering_value {
+                                            self.write_buffered_attribute_value(out, 0)?;
+                                        }
+
                                         out.push_bytes(&text_bytes[start..p])?;
                                         start = p + 1;
 
                                         self.last_space = e;
                                         self.step = Step::StartTagIn;
-                                    } else if self.in_attribute_type {
-                                        self.attribute_type.push(e);
+                                    } else if self.buffering_value {
+                                        debug_assert_eq!(start, p);
+                                        start = p + 1;
+
+                                        self.attribute_value.push(e);
+                                    } else if self.capture_attribute_value {
+                                        self.attribute_value.push(e);
                                     }
                                 },
                             }
@@ -595,13 +1830,24 @@ impl HTMLMinifierHelper {
                         Step::EndTag => {
                             // </a?
                             if is_whitespace(e) {
-                                out.push_bytes(&text_bytes[start..p])?;
+                                if self.holding_tag {
+                                    self.write_held_tag(out, &text_bytes[start..p], true)?;
+                                } else {
+                                    out.push_bytes(&text_bytes[start..p])?;
+                                }
+
                                 start = p + 1;
 
                                 self.step = Step::TagEnd;
                             } else if e == b'>' {
-                                self.last_space = 0;
-                                self.step = Step::InitialRemainOneWhitespace;
+                                if self.holding_tag {
+                                    self.write_held_tag(out, &text_bytes[start..p], true)?;
+                                    start = if self.omitting_tag { p + 1 } else { p };
+                                }
+
+                                self.step = self.get_step_after_tag();
+                            } else {
+                                self.tag.push(e.to_ascii_lowercase());
                             }
                         },
                         Step::TagEnd => {
@@ -609,8 +1855,11 @@ impl HTMLMinifierHelper {
                             // </a ?
                             match e {
                                 b'>' => {
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
+                                    if self.omitting_tag {
+                                        start = p + 1;
+                                    }
+
+                                    self.step = self.get_step_after_tag();
                                 },
                                 _ => {
                                     out.push_bytes(&text_bytes[start..p])?;
@@ -643,12 +1892,16 @@ impl HTMLMinifierHelper {
                                     },
                                     1 => match e {
                                         b'-' => {
-                                            if !self.remove_comments {
+                                            if !self.options.remove_comments
+                                                && !self.buffers_comments()
+                                            {
                                                 out.push_bytes(b"<!--")?;
                                             }
 
                                             start = p + 1;
 
+                                            self.buffer.clear();
+
                                             self.step_counter = 0;
                                             self.step = Step::Comment;
                                         },
@@ -665,7 +1918,12 @@ impl HTMLMinifierHelper {
                         },
                         Step::Comment => {
                             // <!--?
-                            if self.remove_comments {
+                            if self.buffers_comments() {
+                                debug_assert_eq!(start, p);
+                                start = p + 1;
+
+                                self.buffer.push(e);
+                            } else if self.options.remove_comments {
                                 debug_assert_eq!(start, p);
                                 start = p + 1;
                             }
@@ -683,6 +1941,13 @@ impl HTMLMinifierHelper {
                                 2 => {
                                     match e {
                                         b'>' => {
+                                            if self.buffers_comments() {
+                                                self.write_filtered_comment(out)?;
+                                            } else if !self.options.remove_comments {
+                                                out.push_bytes(&text_bytes[start..=p])?;
+                                                start = p + 1;
+                                            }
+
                                             if self.last_space > 0 {
                                                 self.last_space = 0;
 
@@ -698,391 +1963,40 @@ impl HTMLMinifierHelper {
                                 _ => unreachable!(),
                             }
                         },
-                        Step::ScriptDefault => match self.step_counter {
-                            0 => {
-                                if e == b'<' {
-                                    self.step_counter = 1;
-                                }
-                            },
-                            1 => match e {
-                                b'/' => self.step_counter = 2,
-                                _ => self.step_counter = 0,
-                            },
-                            2 => match e {
-                                b's' | b'S' => self.step_counter = 3,
-                                _ => self.step_counter = 0,
-                            },
-                            3 => match e {
-                                b'c' | b'C' => self.step_counter = 4,
-                                _ => self.step_counter = 0,
-                            },
-                            4 => match e {
-                                b'r' | b'R' => self.step_counter = 5,
-                                _ => self.step_counter = 0,
-                            },
-                            5 => match e {
-                                b'i' | b'I' => self.step_counter = 6,
-                                _ => self.step_counter = 0,
-                            },
-                            6 => match e {
-                                b'p' | b'P' => self.step_counter = 7,
-                                _ => self.step_counter = 0,
-                            },
-                            7 => match e {
-                                b't' | b'T' => self.step_counter = 8,
-                                _ => self.step_counter = 0,
-                            },
-                            8 => match e {
-                                b'>' => {
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
-                                },
-                                _ => {
-                                    if is_whitespace(e) {
-                                        out.push_bytes(&text_bytes[start..p])?;
-                                        start = p + 1;
-
-                                        self.step = Step::TagEnd;
-                                    } else {
-                                        self.step_counter = 0;
-                                    }
-                                },
-                            },
-                            _ => unreachable!(),
-                        },
-                        Step::ScriptJavaScript => match self.step_counter {
-                            0 => {
-                                if e == b'<' {
-                                    self.step_counter = 1;
-                                }
-                            },
-                            1 => match e {
-                                b'/' => self.step_counter = 2,
-                                _ => self.step_counter = 0,
-                            },
-                            2 => match e {
-                                b's' | b'S' => self.step_counter = 3,
-                                _ => self.step_counter = 0,
-                            },
-                            3 => match e {
-                                b'c' | b'C' => self.step_counter = 4,
-                                _ => self.step_counter = 0,
-                            },
-                            4 => match e {
-                                b'r' | b'R' => self.step_counter = 5,
-                                _ => self.step_counter = 0,
-                            },
-                            5 => match e {
-                                b'i' | b'I' => self.step_counter = 6,
-                                _ => self.step_counter = 0,
-                            },
-                            6 => match e {
-                                b'p' | b'P' => self.step_counter = 7,
-                                _ => self.step_counter = 0,
-                            },
-                            7 => match e {
-                                b't' | b'T' => self.step_counter = 8,
-                                _ => self.step_counter = 0,
-                            },
-                            8 => match e {
-                                b'>' => {
+                        Step::EmbeddedCode => {
+                            if self.match_end_tag(e) {
+                                if e == b'>' {
                                     self.buffer.extend_from_slice(&text_bytes[start..=p]);
                                     start = p + 1;
 
-                                    let script_length = self.buffer.len() - 9;
-
-                                    let minified_js = js::minify(unsafe {
-                                        from_utf8_unchecked(&self.buffer[..script_length])
-                                    });
-                                    out.push_bytes(minified_js.to_string().as_bytes())?;
-                                    out.push_bytes(&self.buffer[script_length..])?;
-
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
-                                },
-                                _ => {
-                                    if is_whitespace(e) {
-                                        self.buffer.extend_from_slice(&text_bytes[start..p]);
-                                        start = p + 1;
-
-                                        let buffer_length = self.buffer.len();
-                                        let script_length = buffer_length - 8;
-
-                                        let minified_js = js::minify(unsafe {
-                                            from_utf8_unchecked(&self.buffer[..script_length])
-                                        });
-                                        out.push_bytes(minified_js.to_string().as_bytes())?;
-                                        out.push_bytes(&self.buffer[script_length..])?;
+                                    let code_length = self.buffer.len() - self.tag.len() - 3;
 
-                                        self.step = Step::TagEnd;
-                                    } else {
-                                        self.step_counter = 0;
-                                    }
-                                },
-                            },
-                            _ => unreachable!(),
-                        },
-                        Step::StyleDefault => match self.step_counter {
-                            0 => {
-                                if e == b'<' {
-                                    self.step_counter = 1;
-                                }
-                            },
-                            1 => match e {
-                                b'/' => self.step_counter = 2,
-                                _ => self.step_counter = 0,
-                            },
-                            2 => match e {
-                                b's' | b'S' => self.step_counter = 3,
-                                _ => self.step_counter = 0,
-                            },
-                            3 => match e {
-                                b't' | b'T' => self.step_counter = 4,
-                                _ => self.step_counter = 0,
-                            },
-                            4 => match e {
-                                b'y' | b'Y' => self.step_counter = 5,
-                                _ => self.step_counter = 0,
-                            },
-                            5 => match e {
-                                b'l' | b'L' => self.step_counter = 6,
-                                _ => self.step_counter = 0,
-                            },
-                            6 => match e {
-                                b'e' | b'E' => self.step_counter = 7,
-                                _ => self.step_counter = 0,
-                            },
-                            7 => match e {
-                                b'>' => {
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
-                                },
-                                _ => {
-                                    if is_whitespace(e) {
-                                        out.push_bytes(&text_bytes[start..p])?;
-                                        start = p + 1;
+                                    self.write_minified_code(out, code_length)?;
 
-                                        self.step = Step::TagEnd;
-                                    } else {
-                                        self.step_counter = 0;
-                                    }
-                                },
-                            },
-                            _ => unreachable!(),
-                        },
-                        Step::StyleCSS => match self.step_counter {
-                            0 => {
-                                if e == b'<' {
-                                    self.step_counter = 1;
-                                }
-                            },
-                            1 => match e {
-                                b'/' => self.step_counter = 2,
-                                _ => self.step_counter = 0,
-                            },
-                            2 => match e {
-                                b's' | b'S' => self.step_counter = 3,
-                                _ => self.step_counter = 0,
-                            },
-                            3 => match e {
-                                b't' | b'T' => self.step_counter = 4,
-                                _ => self.step_counter = 0,
-                            },
-                            4 => match e {
-                                b'y' | b'Y' => self.step_counter = 5,
-                                _ => self.step_counter = 0,
-                            },
-                            5 => match e {
-                                b'l' | b'L' => self.step_counter = 6,
-                                _ => self.step_counter = 0,
-                            },
-                            6 => match e {
-                                b'e' | b'E' => self.step_counter = 7,
-                                _ => self.step_counter = 0,
-                            },
-                            7 => match e {
-                                b'>' => {
-                                    self.buffer.extend_from_slice(&text_bytes[start..=p]);
+                                    self.step = self.get_step_after_tag();
+                                } else {
+                                    self.buffer.extend_from_slice(&text_bytes[start..p]);
                                     start = p + 1;
 
-                                    let script_length = self.buffer.len() - 8;
-
-                                    let minified_css = css::minify(unsafe {
-                                        from_utf8_unchecked(&self.buffer[..script_length])
-                                    })
-                                    .map_err(HTMLMinifierError::CSSError)?;
-                                    out.push_bytes(minified_css.to_string().as_bytes())?;
-                                    out.push_bytes(&self.buffer[script_length..])?;
-
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
-                                },
-                                _ => {
-                                    if is_whitespace(e) {
-                                        self.buffer.extend_from_slice(&text_bytes[start..p]);
-                                        start = p + 1;
-
-                                        let buffer_length = self.buffer.len();
-                                        let script_length = buffer_length - 7;
+                                    let code_length = self.buffer.len() - self.tag.len() - 2;
 
-                                        let minified_css = css::minify(unsafe {
-                                            from_utf8_unchecked(&self.buffer[..script_length])
-                                        })
-                                        .map_err(HTMLMinifierError::CSSError)?;
-                                        out.push_bytes(minified_css.to_string().as_bytes())?;
-                                        out.push_bytes(&self.buffer[script_length..])?;
+                                    self.write_minified_code(out, code_length)?;
 
-                                        self.step = Step::TagEnd;
-                                    } else {
-                                        self.step_counter = 0;
-                                    }
-                                },
-                            },
-                            _ => unreachable!(),
-                        },
-                        Step::Pre => match self.step_counter {
-                            0 => {
-                                if e == b'<' {
-                                    self.step_counter = 1;
+                                    self.step = Step::TagEnd;
                                 }
-                            },
-                            1 => match e {
-                                b'/' => self.step_counter = 2,
-                                _ => self.step_counter = 0,
-                            },
-                            2 => match e {
-                                b'p' | b'P' => self.step_counter = 3,
-                                _ => self.step_counter = 0,
-                            },
-                            3 => match e {
-                                b'r' | b'R' => self.step_counter = 4,
-                                _ => self.step_counter = 0,
-                            },
-                            4 => match e {
-                                b'e' | b'E' => self.step_counter = 5,
-                                _ => self.step_counter = 0,
-                            },
-                            5 => match e {
-                                b'>' => {
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
-                                },
-                                _ => {
-                                    if is_whitespace(e) {
-                                        out.push_bytes(&text_bytes[start..p])?;
-                                        start = p + 1;
-
-                                        self.step = Step::TagEnd;
-                                    } else {
-                                        self.step_counter = 0;
-                                    }
-                                },
-                            },
-                            _ => unreachable!(),
+                            }
                         },
-                        Step::Code => match self.step_counter {
-                            0 => {
-                                if e == b'<' {
-                                    self.step_counter = 1;
-                                }
-                            },
-                            1 => match e {
-                                b'/' => self.step_counter = 2,
-                                _ => self.step_counter = 0,
-                            },
-                            2 => match e {
-                                b'c' | b'C' => self.step_counter = 3,
-                                _ => self.step_counter = 0,
-                            },
-                            3 => match e {
-                                b'o' | b'O' => self.step_counter = 4,
-                                _ => self.step_counter = 0,
-                            },
-                            4 => match e {
-                                b'd' | b'D' => self.step_counter = 5,
-                                _ => self.step_counter = 0,
-                            },
-                            5 => match e {
-                                b'e' | b'E' => self.step_counter = 6,
-                                _ => self.step_counter = 0,
-                            },
-                            6 => match e {
-                                b'>' => {
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
-                                },
-                                _ => {
-                                    if is_whitespace(e) {
-                                        out.push_bytes(&text_bytes[start..p])?;
-                                        start = p + 1;
+                        Step::Raw => {
+                            if self.match_end_tag(e) {
+                                if e == b'>' {
+                                    self.step = self.get_step_after_tag();
+                                } else {
+                                    out.push_bytes(&text_bytes[start..p])?;
+                                    start = p + 1;
 
-                                        self.step = Step::TagEnd;
-                                    } else {
-                                        self.step_counter = 0;
-                                    }
-                                },
-                            },
-                            _ => unreachable!(),
-                        },
-                        Step::Textarea => match self.step_counter {
-                            0 => {
-                                if e == b'<' {
-                                    self.step_counter = 1;
+                                    self.step = Step::TagEnd;
                                 }
-                            },
-                            1 => match e {
-                                b'/' => self.step_counter = 2,
-                                _ => self.step_counter = 0,
-                            },
-                            2 => match e {
-                                b't' | b'T' => self.step_counter = 3,
-                                _ => self.step_counter = 0,
-                            },
-                            3 => match e {
-                                b'e' | b'E' => self.step_counter = 4,
-                                _ => self.step_counter = 0,
-                            },
-                            4 => match e {
-                                b'x' | b'X' => self.step_counter = 5,
-                                _ => self.step_counter = 0,
-                            },
-                            5 => match e {
-                                b't' | b'T' => self.step_counter = 6,
-                                _ => self.step_counter = 0,
-                            },
-                            6 => match e {
-                                b'a' | b'A' => self.step_counter = 7,
-                                _ => self.step_counter = 0,
-                            },
-                            7 => match e {
-                                b'r' | b'R' => self.step_counter = 8,
-                                _ => self.step_counter = 0,
-                            },
-                            8 => match e {
-                                b'e' | b'E' => self.step_counter = 9,
-                                _ => self.step_counter = 0,
-                            },
-                            9 => match e {
-                                b'a' | b'A' => self.step_counter = 10,
-                                _ => self.step_counter = 0,
-                            },
-                            10 => match e {
-                                b'>' => {
-                                    self.last_space = 0;
-                                    self.step = Step::InitialRemainOneWhitespace;
-                                },
-                                _ => {
-                                    if is_whitespace(e) {
-                                        out.push_bytes(&text_bytes[start..p])?;
-                                        start = p + 1;
-
-                                        self.step = Step::TagEnd;
-                                    } else {
-                                        self.step_counter = 0;
-                                    }
-                                },
-                            },
-                            _ => unreachable!(),
+                            }
                         },
                     }
                 }
@@ -1091,20 +2005,22 @@ impl HTMLMinifierHelper {
                 match self.step {
                     Step::Initial => {
                         // ?
+                        self.write_pending(out)?;
+
                         self.last_space = 0;
                         self.step = Step::InitialRemainOneWhitespace;
                     },
                     Step::InitialRemainOneWhitespace => {
                         // a?
+                        if !self.pending_tag.is_empty() {
+                            self.write_pending(out)?;
+                        }
+
                         self.last_space = 0;
                     },
                     Step::InitialIgnoreWhitespace => {
                         // a ?
-                        if self.last_space == b'\n' {
-                            out.push(b'\n')?;
-                        } else if self.last_space > 0 {
-                            out.push(b' ')?;
-                        }
+                        self.write_pending(out)?;
 
                         self.last_space = 0;
                         self.step = Step::InitialRemainOneWhitespace;
@@ -1114,6 +2030,10 @@ impl HTMLMinifierHelper {
                         // To `InitialRemainOneWhitespace`.
                         debug_assert_eq!(start, p);
 
+                        if self.holds_whitespace() {
+                            self.write_pending(out)?;
+                        }
+
                         out.push(b'<')?;
 
                         self.last_space = 0;
@@ -1122,6 +2042,10 @@ impl HTMLMinifierHelper {
                     Step::EndTagInitial => {
                         // </?
                         // To `InitialRemainOneWhitespace`.
+                        if self.holds_whitespace() {
+                            self.write_pending(out)?;
+                        }
+
                         out.push_bytes(b"</")?;
 
                         self.last_space = 0;
@@ -1131,28 +2055,58 @@ impl HTMLMinifierHelper {
                         // <a?
                         // </a?
                         // To `InitialRemainOneWhitespace`.
+                        if self.holding_tag {
+                            self.write_held_tag(out, &[], false)?;
+                        }
+
                         self.last_space = 0;
                         self.step = Step::InitialRemainOneWhitespace;
                     },
                     Step::StartTagIn => {
                         // <a ?
-                        out.push(b' ')?;
+                        if self.defers_attributes() {
+                            debug_assert_eq!(start, p);
+                            start = p + 1;
 
-                        self.buffer.clear();
-                        self.buffer.push(e);
+                            self.start_attribute(e);
+                        } else {
+                            if self.omitting_tag {
+                                self.write_omitted_start_tag(out)?;
+                            }
+
+                            out.push(b' ')?;
+
+                            self.buffer.clear();
+                            self.buffer.push(e);
+                        }
 
                         self.step = Step::StartTagAttributeName;
                     },
                     Step::StartTagAttributeName => {
                         // <a a?
                         self.buffer.push(e);
+
+                        if self.defers_attributes() {
+                            debug_assert_eq!(start, p);
+                            start = p + 1;
+
+                            self.attribute_name.push(e);
+                        }
                     },
                     Step::StartTagAttributeNameWaitingValue => {
                         // <a a ?
-                        out.push(b' ')?;
+                        if self.defers_attributes() {
+                            debug_assert_eq!(start, p);
+                            start = p + 1;
 
-                        self.buffer.clear();
-                        self.buffer.push(e);
+                            self.finish_attribute(false, 0);
+                            self.start_attribute(e);
+                        } else {
+                            out.push(b' ')?;
+
+                            self.buffer.clear();
+                            self.buffer.push(e);
+                        }
 
                         self.step = Step::StartTagAttributeName;
                     },
@@ -1160,18 +2114,29 @@ impl HTMLMinifierHelper {
                         // <a a=?
                         debug_assert_eq!(start, p);
 
-                        if self.in_attribute_type {
-                            self.attribute_type.push(e);
+                        if self.capture_attribute_value || self.buffering_value {
+                            self.attribute_value.push(e);
                         }
 
-                        out.push(b'=')?;
+                        if self.buffering_value {
+                            start = p + 1;
+                        } else {
+                            out.push(b'=')?;
+                        }
 
                         self.step = Step::StartTagUnquotedAttributeValue;
                     },
                     Step::StartTagQuotedAttributeValue => {
                         // <a a="?
                         // <a a='?
-                        if self.quoted_value_empty {
+                        if self.buffering_value {
+                            debug_assert_eq!(start, p);
+                            start = p + 1;
+
+                            if self.quoted_value_spacing {
+                                self.attribute_value.push(b' ');
+                            }
+                        } else if self.quoted_value_empty {
                             self.quoted_value_empty = false;
 
                             out.push_bytes(&[b'=', self.quote])?;
@@ -1179,15 +2144,20 @@ impl HTMLMinifierHelper {
 
                         self.quoted_value_spacing = false;
 
-                        if self.in_attribute_type {
-                            self.attribute_type.push(e);
+                        if self.capture_attribute_value || self.buffering_value {
+                            self.attribute_value.push(e);
                         }
                     },
                     Step::StartTagUnquotedAttributeValue => {
                         // <a a=v?
                         // <a a=v?
-                        if self.in_attribute_type {
-                            self.attribute_type.push(e);
+                        if self.buffering_value {
+                            debug_assert_eq!(start, p);
+                            start = p + 1;
+
+                            self.attribute_value.push(e);
+                        } else if self.capture_attribute_value {
+                            self.attribute_value.push(e);
                         }
                     },
                     Step::TagEnd => {
@@ -1206,20 +2176,19 @@ impl HTMLMinifierHelper {
                     },
                     Step::Comment => {
                         // <!--?
-                        if self.remove_comments {
+                        if self.buffers_comments() {
+                            debug_assert_eq!(start, p);
+                            start = p + 1;
+
+                            self.buffer.push(e);
+                        } else if self.options.remove_comments {
                             debug_assert_eq!(start, p);
                             start = p + 1;
                         }
 
                         self.step_counter = 0;
                     },
-                    Step::ScriptDefault
-                    | Step::StyleDefault
-                    | Step::Pre
-                    | Step::Code
-                    | Step::Textarea
-                    | Step::ScriptJavaScript
-                    | Step::StyleCSS => {
+                    Step::EmbeddedCode | Step::Raw => {
                         self.step_counter = 0;
                     },
                 }
@@ -1229,12 +2198,215 @@ impl HTMLMinifierHelper {
         }
 
         match self.step {
-            Step::ScriptJavaScript | Step::StyleCSS => {
+            Step::EmbeddedCode => {
                 self.buffer.extend_from_slice(&text_bytes[start..p]);
             },
+            Step::StartTag | Step::EndTag if self.holding_tag => {
+                self.tag_raw.extend_from_slice(&text_bytes[start..p]);
+            },
             _ => out.push_bytes(&text_bytes[start..p])?,
         }
 
+        self.locate(text_bytes, text_length);
+
+        Ok(())
+    }
+
+    /// Signal the end of the input. The buffered content (e.g. the code of an unclosed `script` element) and the pending tags are written. An unterminated comment, tag or element is reported as a diagnostic if the `collect_diagnostics` option is enabled, otherwise it is returned as `HTMLMinifierError::Unterminated` after the output has been written.
+    ///
+    /// Call `reset` before digesting another HTML text.
+    pub fn finish<W: HTMLWriter>(&mut self, out: &mut W) -> Result<(), HTMLMinifierError> {
+        let unterminated = match self.step {
+            Step::Initial | Step::InitialRemainOneWhitespace | Step::InitialIgnoreWhitespace => {
+                // the start tags of `html`, `head` and `body` can be omitted if the document ends, and so can all the optional end tags
+                if !self.pending_tag.is_empty()
+                    && self.pending_start_tag
+                    && !matches!(self.pending_tag_name.as_slice(), b"html" | b"head" | b"body")
+                {
+                    self.write_pending_tag(out)?;
+                }
+
+                None
+            },
+            Step::StartTagInitial | Step::EndTagInitial => {
+                self.write_pending(out)?;
+
+                out.push_bytes(if self.step == Step::StartTagInitial { b"<" } else { b"</" })?;
+
+                Some((DiagnosticKind::UnterminatedTag, String::from("unterminated tag")))
+            },
+            Step::StartTag
+            | Step::StartTagIn
+            | Step::StartTagAttributeName
+            | Step::StartTagAttributeNameWaitingValue
+            | Step::StartTagAttributeValueInitial
+            | Step::StartTagUnquotedAttributeValue
+            | Step::StartTagQuotedAttributeValue
+            | Step::EndTag
+            | Step::TagEnd => {
+                if self.holding_tag {
+                    self.write_held_tag(out, &[], false)?;
+                } else if self.omitting_tag && self.pending_start_tag && !self.in_end_tag {
+                    self.write_omitted_start_tag(out)?;
+                }
+
+                if !self.in_end_tag {
+                    self.write_unterminated_attributes(out)?;
+                }
+
+                Some((
+                    DiagnosticKind::UnterminatedTag,
+                    format!("unterminated tag `{}`", String::from_utf8_lossy(&self.tag)),
+                ))
+            },
+            Step::Doctype => Some((
+                DiagnosticKind::UnterminatedTag,
+                String::from("unterminated markup declaration"),
+            )),
+            Step::Comment => {
+                // a kept comment is written as it is
+                if !self.options.remove_comments && self.buffers_comments() {
+                    out.push_bytes(b"<!--")?;
+                    out.push_bytes(&self.buffer)?;
+                }
+
+                Some((DiagnosticKind::UnterminatedComment, String::from("unterminated comment")))
+            },
+            Step::EmbeddedCode | Step::Raw => {
+                if self.step == Step::EmbeddedCode {
+                    // the partially matched end tag is not a part of the code
+                    let length = self.buffer.len() - self.step_counter;
+
+                    self.write_minified_code(out, length)?;
+                }
+
+                Some((
+                    DiagnosticKind::UnclosedElement,
+                    format!("unclosed element `{}`", String::from_utf8_lossy(&self.tag)),
+                ))
+            },
+        };
+
+        self.step = Step::default();
+        self.last_space = 0;
+        self.holding_tag = false;
+        self.omitting_tag = false;
+        self.after_table_row = false;
+        self.pending_tag.clear();
+        self.embedded_minifier = None;
+        self.buffer.clear();
+
+        if let Some((kind, message)) = unterminated {
+            let diagnostic = Diagnostic {
+                kind,
+                position: self.tag_position,
+                message,
+            };
+
+            if self.options.collect_diagnostics {
+                self.diagnostics.push(diagnostic);
+            } else {
+                return Err(HTMLMinifierError::Unterminated(diagnostic));
+            }
+        }
+
         Ok(())
     }
 }
+
+/// Write the attribute value. If `remove_quotes` is `true`, the quotes are removed if it is safe, otherwise the quote needing less escaping is used. An unquoted value which is no longer safe to be unquoted is quoted. Return whether the value is written unquoted.
+fn write_attribute_value(
+    out: &mut impl HTMLWriter,
+    value: &[u8],
+    quote: u8,
+    remove_quotes: bool,
+) -> Result<bool, HTMLMinifierError> {
+    if (quote == 0 || remove_quotes) && can_unquote_attribute_value(value) {
+        out.push_bytes(value)?;
+
+        return Ok(true);
+    }
+
+    let quote = if remove_quotes || quote == 0 {
+        let mut double_quotes = 0;
+        let mut single_quotes = 0;
+
+        let mut i = 0;
+
+        while i < value.len() {
+            let (quote, length) = match_quote(&value[i..]);
+
+            match quote {
+                b'"' => double_quotes += 1,
+                b'\'' => single_quotes += 1,
+                _ => (),
+            }
+
+            i += length;
+        }
+
+        if double_quotes > single_quotes { b'\'' } else { b'"' }
+    } else {
+        quote
+    };
+
+    out.push(quote)?;
+
+    let mut i = 0;
+
+    // a rewritten value may contain the quote, and the original value never does
+    while i < value.len() {
+        let (c, length) = match_quote(&value[i..]);
+
+        if c == quote && (remove_quotes || length == 1) {
+            out.push_bytes(if quote == b'"' { b"&#34;" } else { b"&#39;" })?;
+        } else if c > 0 && remove_quotes {
+            out.push(c)?;
+        } else {
+            out.push_bytes(&value[i..i + length])?;
+        }
+
+        i += length;
+    }
+
+    out.push(quote)?;
+
+    Ok(false)
+}
+
+/// Minify CSS declarations by wrapping them in a rule. Return `None` if they cannot be minified.
+fn minify_style_declarations(declarations: &str) -> Option<String> {
+    let rule = format!("a{{{declarations}}}");
+
+    let minified = css::minify(&rule).ok()?.to_string();
+
+    let declarations = minified.strip_prefix("a{")?.strip_suffix('}')?;
+
+    // the semicolon after the last declaration is optional
+    let declarations = match declarations.strip_suffix(';') {
+        Some(d) if !d.ends_with('\\') => d,
+        _ => declarations,
+    };
+
+    Some(String::from(declarations))
+}
+
+/// Minify the JS code in a `javascript:` URL. Return `None` if the URL does not use the `javascript:` scheme or the code cannot be minified safely.
+fn minify_javascript_url(url: &str) -> Option<String> {
+    const SCHEME: &str = "javascript:";
+
+    let url = url.trim_matches(|c: char| c.is_ascii() && is_whitespace(c as u8));
+
+    if !url.get(..SCHEME.len())?.eq_ignore_ascii_case(SCHEME) {
+        return None;
+    }
+
+    let code = &url[SCHEME.len()..];
+
+    // the code is percent-decoded before being executed, so `%` cannot be handled as an operator
+    if code.contains('%') {
+        return None;
+    }
+
+    Some(format!("{SCHEME}{}", js::minify(code)))
+}
@@ -0,0 +1,695 @@
+use educe::Educe;
+
+use crate::functions::mime_type_essence;
+
+/// How to collapse a run of whitespaces (spaces, tabs and newlines) which cannot be removed.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum WhitespaceCollapse {
+    /// Collapse to a single `'\n'` if the run contains a newline, otherwise to a single `'\x20'`. The output is smaller than the original one and still readable.
+    #[default]
+    Newline,
+    /// Always collapse to a single `'\x20'` for the smallest output. Use it with the `aggressive_whitespace` option to remove whitespaces where it is safe.
+    Space,
+    /// Collapse to all the newlines the run contains, otherwise to a single `'\x20'`. Only the newlines of whitespaces in text are kept. Those inside tags or around removed comments are not, so line numbers can still change.
+    PreserveLineBreaks,
+}
+
+/// A rule to match elements whose whitespaces are significant (e.g. elements styled with `white-space: pre`). The content of the matched elements is preserved as it is. Void elements (e.g. `input`) and elements whose end tags can be omitted (e.g. `p` and `li`) are never matched.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum WhitespacePreservingRule {
+    /// Match elements whose `class` attribute contains the class name.
+    Class(String),
+    /// Match elements carrying the attribute (the first field) whose value contains the text (the second field). Whitespaces are removed and letters are lowercased before comparing, so `white-space:pre` matches `style="White-Space: pre-wrap"`. An empty text matches any value.
+    Attribute(String, String),
+}
+
+impl WhitespacePreservingRule {
+    /// Create a rule matching elements whose `class` attribute contains the class name.
+    #[inline]
+    pub fn class<S: Into<String>>(class_name: S) -> Self {
+        Self::Class(class_name.into())
+    }
+
+    /// Create a rule matching elements carrying the attribute whose value contains the text.
+    #[inline]
+    pub fn attribute<N: Into<String>, S: Into<String>>(name: N, text: S) -> Self {
+        Self::Attribute(name.into(), text.into())
+    }
+
+    #[inline]
+    pub(crate) fn get_attribute_name(&self) -> &str {
+        match self {
+            Self::Class(_) => "class",
+            Self::Attribute(name, _) => name.as_str(),
+        }
+    }
+
+    /// `name` must be lowercased and `value` must be decoded. An empty `value` with a `Class` rule never matches.
+    pub(crate) fn matches(&self, name: &[u8], value: &str) -> bool {
+        match self {
+            Self::Class(class_name) => {
+                name == b"class" && value.split_ascii_whitespace().any(|token| token == class_name)
+            },
+            Self::Attribute(attribute_name, text) => {
+                attribute_name.as_bytes().eq_ignore_ascii_case(name)
+                    && (text.is_empty()
+                        || normalize_rule_text(value).contains(&normalize_rule_text(text)))
+            },
+        }
+    }
+}
+
+#[inline]
+fn normalize_rule_text(text: &str) -> String {
+    text.chars().filter(|c| !c.is_ascii_whitespace()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// What to do when the code embedded in a `script` or `style` element cannot be minified.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum EmbeddedErrorPolicy {
+    /// Return the error from `digest`. The output is incomplete and the minification cannot be continued.
+    #[default]
+    Abort,
+    /// Write the original code and continue.
+    Verbatim,
+    /// Write the original code, record a warning which can be retrieved by `HTMLMinifierHelper::get_warnings` and continue.
+    Warn,
+}
+
+/// How to normalize the value of an attribute.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AttributeNormalization {
+    /// A set of space-separated tokens, e.g. `class`, `rel` and `headers`. Whitespaces are trimmed and collapsed to a single `'\x20'`.
+    TokenList,
+    /// A comma-separated list, e.g. `srcset`, `sizes` and `accept`. Whitespaces are trimmed and collapsed to a single `'\x20'`, and removed before commas.
+    CommaSeparatedList,
+    /// A URL potentially surrounded by whitespaces, e.g. `href` and `src`. Whitespaces are trimmed.
+    TrimmedURL,
+    /// An enumerated keyword, e.g. `method`, `dir` and `crossorigin`. Whitespaces are trimmed and letters are lowercased.
+    CaseInsensitiveKeyword,
+}
+
+/// Refer to https://html.spec.whatwg.org/multipage/indices.html#attributes-3
+fn html_attribute_normalizations() -> Vec<(String, AttributeNormalization)> {
+    const TOKEN_LISTS: [&str; 18] = [
+        "class",
+        "rel",
+        "headers",
+        "accesskey",
+        "sandbox",
+        "ping",
+        "itemprop",
+        "itemref",
+        "itemtype",
+        "blocking",
+        "autocomplete",
+        "aria-controls",
+        "aria-describedby",
+        "aria-details",
+        "aria-errormessage",
+        "aria-flowto",
+        "aria-labelledby",
+        "aria-owns",
+    ];
+    const COMMA_SEPARATED_LISTS: [&str; 6] =
+        ["srcset", "sizes", "imagesrcset", "imagesizes", "accept", "coords"];
+    const URLS: [&str; 10] = [
+        "href",
+        "src",
+        "action",
+        "formaction",
+        "cite",
+        "poster",
+        "data",
+        "longdesc",
+        "manifest",
+        "itemid",
+    ];
+    const KEYWORDS: [&str; 20] = [
+        "method",
+        "formmethod",
+        "enctype",
+        "formenctype",
+        "dir",
+        "shape",
+        "loading",
+        "decoding",
+        "fetchpriority",
+        "crossorigin",
+        "referrerpolicy",
+        "wrap",
+        "preload",
+        "inputmode",
+        "enterkeyhint",
+        "autocapitalize",
+        "draggable",
+        "spellcheck",
+        "contenteditable",
+        "translate",
+    ];
+
+    let mut normalizations = Vec::new();
+
+    for (names, normalization) in [
+        (&TOKEN_LISTS[..], AttributeNormalization::TokenList),
+        (&COMMA_SEPARATED_LISTS[..], AttributeNormalization::CommaSeparatedList),
+        (&URLS[..], AttributeNormalization::TrimmedURL),
+        (&KEYWORDS[..], AttributeNormalization::CaseInsensitiveKeyword),
+    ] {
+        normalizations.extend(names.iter().map(|name| (String::from(*name), normalization)));
+    }
+
+    normalizations
+}
+
+/// Options for minifying HTML. They can be shared by `HTMLMinifier`, `HTMLMinifierHelper` and the `minify_with_options` function.
+///
+/// ```rust
+/// use html_minifier::HTMLMinifierOptions;
+///
+/// let options =
+///     HTMLMinifierOptions::new().remove_comments(false).minify_code(false);
+///
+/// assert!(!options.get_remove_comments());
+/// assert!(!options.get_minify_code());
+/// ```
+#[derive(Educe, Debug, Clone, Eq, PartialEq)]
+#[educe(Default(new))]
+pub struct HTMLMinifierOptions {
+    #[educe(Default = true)]
+    pub(crate) remove_comments:             bool,
+    #[educe(Default = true)]
+    pub(crate) minify_code:                 bool,
+    #[educe(Default(expression = vec![String::from("pre"), String::from("textarea")]))]
+    pub(crate) raw_elements:                Vec<String>,
+    pub(crate) raw_attributes:              Vec<String>,
+    pub(crate) whitespace_preserving_rules: Vec<WhitespacePreservingRule>,
+    pub(crate) aggressive_whitespace:       bool,
+    pub(crate) whitespace_collapse:         WhitespaceCollapse,
+    pub(crate) remove_optional_end_tags:    bool,
+    pub(crate) remove_optional_start_tags:  bool,
+    pub(crate) remove_attribute_quotes:     bool,
+    pub(crate) collapse_boolean_attributes: bool,
+    pub(crate) remove_redundant_attributes: bool,
+    pub(crate) remove_empty_attributes:     bool,
+    #[educe(Default(expression = vec![String::from("class"), String::from("id"), String::from("style"), String::from("title"), String::from("lang"), String::from("dir"), String::from("on*")]))]
+    pub(crate) empty_attributes:            Vec<String>,
+    pub(crate) deduplicate_class_names:     bool,
+    pub(crate) sort_class_names:            bool,
+    pub(crate) sort_attributes:             bool,
+    #[educe(Default(expression = vec![(String::from("class"), AttributeNormalization::TokenList)]))]
+    pub(crate) attribute_normalizations:    Vec<(String, AttributeNormalization)>,
+    pub(crate) minify_style_attributes:     bool,
+    pub(crate) minify_event_handlers:       bool,
+    pub(crate) minify_javascript_urls:      bool,
+    pub(crate) javascript_types:            Vec<String>,
+    pub(crate) embedded_error_policy:       EmbeddedErrorPolicy,
+    pub(crate) collect_diagnostics:         bool,
+    pub(crate) keep_conditional_comments:   bool,
+    pub(crate) minify_conditional_comments: bool,
+    pub(crate) preserved_comment_prefixes:  Vec<String>,
+}
+
+impl HTMLMinifierOptions {
+    /// Set whether to remove HTML comments. (default: `true`)
+    #[inline]
+    pub const fn remove_comments(mut self, remove_comments: bool) -> Self {
+        self.remove_comments = remove_comments;
+
+        self
+    }
+
+    /// Set whether to keep IE conditional comments (e.g. `<!--[if IE]>...<![endif]-->`) when comments are removed. (default: `false`)
+    #[inline]
+    pub const fn keep_conditional_comments(mut self, keep_conditional_comments: bool) -> Self {
+        self.keep_conditional_comments = keep_conditional_comments;
+
+        self
+    }
+
+    /// Set whether to minify the inner HTML of the kept conditional comments, i.e. all conditional comments if the `remove_comments` option is disabled, otherwise those kept by the `keep_conditional_comments` option. (default: `false`)
+    #[inline]
+    pub const fn minify_conditional_comments(mut self, minify_conditional_comments: bool) -> Self {
+        self.minify_conditional_comments = minify_conditional_comments;
+
+        self
+    }
+
+    /// Set the prefixes of the comments which are kept when comments are removed. Leading whitespaces of comments are ignored when matching. (default: empty)
+    #[inline]
+    pub fn preserved_comment_prefixes<I: IntoIterator<Item = S>, S: AsRef<str>>(
+        mut self,
+        prefixes: I,
+    ) -> Self {
+        self.preserved_comment_prefixes =
+            prefixes.into_iter().map(|e| String::from(e.as_ref())).collect();
+
+        self
+    }
+
+    /// Add a prefix of the comments which are kept when comments are removed, e.g. `#` for SSI directives or `!` for license comments. Knockout bindings need both `ko ` and `/ko` so that the opening comments and the closing comments are kept together.
+    #[inline]
+    pub fn add_preserved_comment_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.preserved_comment_prefixes.push(prefix.into());
+
+        self
+    }
+
+    /// Set whether to minify the content in the `code` element. (default: `true`)
+    #[inline]
+    pub const fn minify_code(mut self, minify_code: bool) -> Self {
+        self.minify_code = minify_code;
+
+        self
+    }
+
+    /// Set whether to minify the CSS declarations in `style` attributes. The original value is kept if it cannot be minified. (default: `false`)
+    #[inline]
+    pub const fn minify_style_attributes(mut self, minify_style_attributes: bool) -> Self {
+        self.minify_style_attributes = minify_style_attributes;
+
+        self
+    }
+
+    /// Set whether to minify the JS code in event handler attributes (e.g. `onclick` and `onload`). (default: `false`)
+    #[inline]
+    pub const fn minify_event_handlers(mut self, minify_event_handlers: bool) -> Self {
+        self.minify_event_handlers = minify_event_handlers;
+
+        self
+    }
+
+    /// Set whether to minify the JS code in `javascript:` URLs of `href`, `src`, `action` and `formaction` attributes. The whitespaces around the scheme are removed as well, e.g. `href=" javascript: void( 0 )"` to `href="javascript:void(0)"`. (default: `false`)
+    #[inline]
+    pub const fn minify_javascript_urls(mut self, minify_javascript_urls: bool) -> Self {
+        self.minify_javascript_urls = minify_javascript_urls;
+
+        self
+    }
+
+    /// Set the extra types of `script` elements whose content is minified as JS code, besides the JavaScript MIME types (e.g. `text/javascript`) and `module`. Types are case-insensitive and their parameters are ignored. (default: empty)
+    #[inline]
+    pub fn javascript_types<I: IntoIterator<Item = S>, S: AsRef<str>>(mut self, types: I) -> Self {
+        self.javascript_types = types
+            .into_iter()
+            .map(|e| mime_type_essence(&e.as_ref().to_ascii_lowercase()).to_string())
+            .collect();
+
+        self
+    }
+
+    /// Add an extra type of `script` elements whose content is minified as JS code, e.g. `text/babel`. The type is case-insensitive.
+    #[inline]
+    pub fn add_javascript_type<S: AsRef<str>>(mut self, r#type: S) -> Self {
+        self.javascript_types
+            .push(mime_type_essence(&r#type.as_ref().to_ascii_lowercase()).to_string());
+
+        self
+    }
+
+    /// Set what to do when the code embedded in a `script` or `style` element cannot be minified. (default: `EmbeddedErrorPolicy::Abort`)
+    #[inline]
+    pub const fn embedded_error_policy(
+        mut self,
+        embedded_error_policy: EmbeddedErrorPolicy,
+    ) -> Self {
+        self.embedded_error_policy = embedded_error_policy;
+
+        self
+    }
+
+    /// Set whether to collect diagnostics about malformed HTML (e.g. duplicate attributes), which can be retrieved by `HTMLMinifierHelper::get_diagnostics`. (default: `false`)
+    #[inline]
+    pub const fn collect_diagnostics(mut self, collect_diagnostics: bool) -> Self {
+        self.collect_diagnostics = collect_diagnostics;
+
+        self
+    }
+
+    /// Set the elements whose content is preserved as it is. Element names are case-insensitive. (default: `pre`, `textarea`)
+    #[inline]
+    pub fn raw_elements<I: IntoIterator<Item = S>, S: AsRef<str>>(mut self, elements: I) -> Self {
+        self.raw_elements = elements.into_iter().map(|e| e.as_ref().to_ascii_lowercase()).collect();
+
+        self
+    }
+
+    /// Add an element whose content is preserved as it is, e.g. `listing`, `xmp` or `x-snippet`. The element name is case-insensitive.
+    #[inline]
+    pub fn add_raw_element<S: AsRef<str>>(mut self, element: S) -> Self {
+        self.raw_elements.push(element.as_ref().to_ascii_lowercase());
+
+        self
+    }
+
+    /// Set the attributes which make the content of the elements carrying them be preserved as it is. Void elements (e.g. `img`) and elements whose end tags can be omitted (e.g. `p` and `li`) are not affected. Attribute names are case-insensitive. (default: empty)
+    #[inline]
+    pub fn raw_attributes<I: IntoIterator<Item = S>, S: AsRef<str>>(
+        mut self,
+        attributes: I,
+    ) -> Self {
+        self.raw_attributes =
+            attributes.into_iter().map(|e| e.as_ref().to_ascii_lowercase()).collect();
+
+        self
+    }
+
+    /// Add an attribute which makes the content of the elements carrying it be preserved as it is, e.g. `data-no-minify`. The attribute name is case-insensitive.
+    #[inline]
+    pub fn add_raw_attribute<S: AsRef<str>>(mut self, attribute: S) -> Self {
+        self.raw_attributes.push(attribute.as_ref().to_ascii_lowercase());
+
+        self
+    }
+
+    /// Set the rules to match elements whose whitespaces are significant. The content of the matched elements is preserved as it is, until their matching end tags. (default: empty)
+    #[inline]
+    pub fn whitespace_preserving_rules<I: IntoIterator<Item = WhitespacePreservingRule>>(
+        mut self,
+        rules: I,
+    ) -> Self {
+        self.whitespace_preserving_rules = rules.into_iter().collect();
+
+        self
+    }
+
+    /// Add a rule to match elements whose whitespaces are significant, e.g. `WhitespacePreservingRule::class("highlight")` or `WhitespacePreservingRule::attribute("style", "white-space:pre")`.
+    #[inline]
+    pub fn add_whitespace_preserving_rule(mut self, rule: WhitespacePreservingRule) -> Self {
+        self.whitespace_preserving_rules.push(rule);
+
+        self
+    }
+
+    /// Set whether to remove whitespaces around block elements (e.g. `div`, `p`, `li`, `head` children and `table` internals), where they are never rendered. Whitespaces around inline elements (e.g. `b`, `a` and `span`) are still collapsed rather than removed because they are significant. (default: `false`)
+    #[inline]
+    pub const fn aggressive_whitespace(mut self, aggressive_whitespace: bool) -> Self {
+        self.aggressive_whitespace = aggressive_whitespace;
+
+        self
+    }
+
+    /// Set how to collapse a run of whitespaces which cannot be removed. (default: `WhitespaceCollapse::Newline`)
+    #[inline]
+    pub const fn whitespace_collapse(mut self, whitespace_collapse: WhitespaceCollapse) -> Self {
+        self.whitespace_collapse = whitespace_collapse;
+
+        self
+    }
+
+    /// Set whether to remove end tags which can be omitted according to the HTML specification, e.g. `</li>`, `</p>`, `</td>`, `</tr>`, `</option>`, `</head>`, `</body>` and `</html>`. An end tag followed by a whitespace is usually kept, so it is recommended to use this with the `aggressive_whitespace` option. (default: `false`)
+    #[inline]
+    pub const fn remove_optional_end_tags(mut self, remove_optional_end_tags: bool) -> Self {
+        self.remove_optional_end_tags = remove_optional_end_tags;
+
+        self
+    }
+
+    /// Set whether to remove start tags which can be omitted according to the HTML specification, i.e. `<html>`, `<head>`, `<body>`, `<colgroup>` and `<tbody>` without attributes, when the elements are implied by their content. (default: `false`)
+    #[inline]
+    pub const fn remove_optional_start_tags(mut self, remove_optional_start_tags: bool) -> Self {
+        self.remove_optional_start_tags = remove_optional_start_tags;
+
+        self
+    }
+
+    /// Set whether to remove the quotes around attribute values which contain no whitespaces, `"`, `'`, `=`, `<`, `>` or `` ` ``. The quote needing less escaping is used for other values. (default: `false`)
+    #[inline]
+    pub const fn remove_attribute_quotes(mut self, remove_attribute_quotes: bool) -> Self {
+        self.remove_attribute_quotes = remove_attribute_quotes;
+
+        self
+    }
+
+    /// Set whether to collapse boolean attributes (e.g. `checked`, `disabled`, `selected`, `async` and `defer`) to their bare names regardless of their values, e.g. `<input checked="checked">` to `<input checked>`. (default: `false`)
+    #[inline]
+    pub const fn collapse_boolean_attributes(mut self, collapse_boolean_attributes: bool) -> Self {
+        self.collapse_boolean_attributes = collapse_boolean_attributes;
+
+        self
+    }
+
+    /// Set whether to remove attributes whose values equal the defaults of the elements, e.g. `type="text/javascript"` of `script`, `type="text/css"` of `style`, `method="get"` of `form` and `type="text"` of `input`. Note that CSS attribute selectors such as `input[type=text]` no longer match the elements. (default: `false`)
+    #[inline]
+    pub const fn remove_redundant_attributes(mut self, remove_redundant_attributes: bool) -> Self {
+        self.remove_redundant_attributes = remove_redundant_attributes;
+
+        self
+    }
+
+    /// Set whether to remove attributes which have no values or have empty values if they are in the empty attributes, e.g. `class=""` and `onclick=""`. (default: `false`)
+    #[inline]
+    pub const fn remove_empty_attributes(mut self, remove_empty_attributes: bool) -> Self {
+        self.remove_empty_attributes = remove_empty_attributes;
+
+        self
+    }
+
+    /// Set the attributes which have no effect when their values are empty. Attribute names are case-insensitive. A name ending with `*` matches all the attributes starting with the rest of it. (default: `class`, `id`, `style`, `title`, `lang`, `dir`, `on*`)
+    #[inline]
+    pub fn empty_attributes<I: IntoIterator<Item = S>, S: AsRef<str>>(
+        mut self,
+        attributes: I,
+    ) -> Self {
+        self.empty_attributes =
+            attributes.into_iter().map(|e| e.as_ref().to_ascii_lowercase()).collect();
+
+        self
+    }
+
+    /// Add an attribute which has no effect when its value is empty, e.g. `alt` or `data-*`. The attribute name is case-insensitive.
+    #[inline]
+    pub fn add_empty_attribute<S: AsRef<str>>(mut self, attribute: S) -> Self {
+        self.empty_attributes.push(attribute.as_ref().to_ascii_lowercase());
+
+        self
+    }
+
+    /// Set how to normalize the values of attributes. Attribute names are case-insensitive. The `type` attribute of `script` and `style` elements is always normalized as a token list. (default: `class` as a token list)
+    #[inline]
+    pub fn attribute_normalizations<
+        I: IntoIterator<Item = (S, AttributeNormalization)>,
+        S: AsRef<str>,
+    >(
+        mut self,
+        normalizations: I,
+    ) -> Self {
+        self.attribute_normalizations = normalizations
+            .into_iter()
+            .map(|(name, normalization)| (name.as_ref().to_ascii_lowercase(), normalization))
+            .collect();
+
+        self
+    }
+
+    /// Set how to normalize the values of the attributes from the HTML specification according to their types, such as `class`, `rel`, `srcset`, `href` and `method`. The current normalizations are replaced.
+    #[inline]
+    pub fn html_attribute_normalizations(mut self) -> Self {
+        self.attribute_normalizations = html_attribute_normalizations();
+
+        self
+    }
+
+    /// Add an attribute whose value is normalized, e.g. `"data-tags"` with `AttributeNormalization::TokenList`. The normalization of the same attribute is replaced. The attribute name is case-insensitive.
+    #[inline]
+    pub fn add_attribute_normalization<S: AsRef<str>>(
+        mut self,
+        attribute: S,
+        normalization: AttributeNormalization,
+    ) -> Self {
+        let attribute = attribute.as_ref().to_ascii_lowercase();
+
+        self.attribute_normalizations.retain(|(name, _)| *name != attribute);
+        self.attribute_normalizations.push((attribute, normalization));
+
+        self
+    }
+
+    /// Set whether to remove duplicate class names in `class` attributes, e.g. `class="a b a"` to `class="a b"`. (default: `false`)
+    #[inline]
+    pub const fn deduplicate_class_names(mut self, deduplicate_class_names: bool) -> Self {
+        self.deduplicate_class_names = deduplicate_class_names;
+
+        self
+    }
+
+    /// Set whether to sort class names in `class` attributes, so that the same set of class names is always written in the same order and the output can be compressed better. (default: `false`)
+    #[inline]
+    pub const fn sort_class_names(mut self, sort_class_names: bool) -> Self {
+        self.sort_class_names = sort_class_names;
+
+        self
+    }
+
+    /// Set whether to sort attributes by their names, so that the same set of attributes is always written in the same order and the output can be compressed better. Duplicate attributes keep their order. (default: `false`)
+    #[inline]
+    pub const fn sort_attributes(mut self, sort_attributes: bool) -> Self {
+        self.sort_attributes = sort_attributes;
+
+        self
+    }
+}
+
+impl HTMLMinifierOptions {
+    /// Get whether to remove HTML comments.
+    #[inline]
+    pub const fn get_remove_comments(&self) -> bool {
+        self.remove_comments
+    }
+
+    /// Get whether to minify the content in the `code` element.
+    #[inline]
+    pub const fn get_minify_code(&self) -> bool {
+        self.minify_code
+    }
+
+    /// Get whether to keep IE conditional comments when comments are removed.
+    #[inline]
+    pub const fn get_keep_conditional_comments(&self) -> bool {
+        self.keep_conditional_comments
+    }
+
+    /// Get whether to minify the inner HTML of the kept conditional comments.
+    #[inline]
+    pub const fn get_minify_conditional_comments(&self) -> bool {
+        self.minify_conditional_comments
+    }
+
+    /// Get the prefixes of the comments which are kept when comments are removed.
+    #[inline]
+    pub fn get_preserved_comment_prefixes(&self) -> &[String] {
+        self.preserved_comment_prefixes.as_slice()
+    }
+
+    /// Get whether to minify the CSS declarations in `style` attributes.
+    #[inline]
+    pub const fn get_minify_style_attributes(&self) -> bool {
+        self.minify_style_attributes
+    }
+
+    /// Get whether to minify the JS code in event handler attributes.
+    #[inline]
+    pub const fn get_minify_event_handlers(&self) -> bool {
+        self.minify_event_handlers
+    }
+
+    /// Get whether to minify the JS code in `javascript:` URLs.
+    #[inline]
+    pub const fn get_minify_javascript_urls(&self) -> bool {
+        self.minify_javascript_urls
+    }
+
+    /// Get the extra types of `script` elements whose content is minified as JS code.
+    #[inline]
+    pub fn get_javascript_types(&self) -> &[String] {
+        self.javascript_types.as_slice()
+    }
+
+    /// Get what to do when the code embedded in a `script` or `style` element cannot be minified.
+    #[inline]
+    pub const fn get_embedded_error_policy(&self) -> EmbeddedErrorPolicy {
+        self.embedded_error_policy
+    }
+
+    /// Get whether to collect diagnostics about malformed HTML.
+    #[inline]
+    pub const fn get_collect_diagnostics(&self) -> bool {
+        self.collect_diagnostics
+    }
+
+    /// Get the elements whose content is preserved as it is.
+    #[inline]
+    pub fn get_raw_elements(&self) -> &[String] {
+        self.raw_elements.as_slice()
+    }
+
+    /// Get the attributes which make the content of the elements carrying them be preserved as it is.
+    #[inline]
+    pub fn get_raw_attributes(&self) -> &[String] {
+        self.raw_attributes.as_slice()
+    }
+
+    /// Get the rules to match elements whose whitespaces are significant.
+    #[inline]
+    pub fn get_whitespace_preserving_rules(&self) -> &[WhitespacePreservingRule] {
+        self.whitespace_preserving_rules.as_slice()
+    }
+
+    /// Get whether to remove whitespaces around block elements.
+    #[inline]
+    pub const fn get_aggressive_whitespace(&self) -> bool {
+        self.aggressive_whitespace
+    }
+
+    /// Get how to collapse a run of whitespaces which cannot be removed.
+    #[inline]
+    pub const fn get_whitespace_collapse(&self) -> WhitespaceCollapse {
+        self.whitespace_collapse
+    }
+
+    /// Get whether to remove end tags which can be omitted.
+    #[inline]
+    pub const fn get_remove_optional_end_tags(&self) -> bool {
+        self.remove_optional_end_tags
+    }
+
+    /// Get whether to remove start tags which can be omitted.
+    #[inline]
+    pub const fn get_remove_optional_start_tags(&self) -> bool {
+        self.remove_optional_start_tags
+    }
+
+    /// Get whether to remove the quotes around attribute values where it is safe.
+    #[inline]
+    pub const fn get_remove_attribute_quotes(&self) -> bool {
+        self.remove_attribute_quotes
+    }
+
+    /// Get whether to collapse boolean attributes to their bare names.
+    #[inline]
+    pub const fn get_collapse_boolean_attributes(&self) -> bool {
+        self.collapse_boolean_attributes
+    }
+
+    /// Get whether to remove attributes whose values equal the defaults of the elements.
+    #[inline]
+    pub const fn get_remove_redundant_attributes(&self) -> bool {
+        self.remove_redundant_attributes
+    }
+
+    /// Get whether to remove empty attributes which have no effect.
+    #[inline]
+    pub const fn get_remove_empty_attributes(&self) -> bool {
+        self.remove_empty_attributes
+    }
+
+    /// Get the attributes which have no effect when their values are empty.
+    #[inline]
+    pub fn get_empty_attributes(&self) -> &[String] {
+        self.empty_attributes.as_slice()
+    }
+
+    /// Get how to normalize the values of attributes.
+    #[inline]
+    pub fn get_attribute_normalizations(&self) -> &[(String, AttributeNormalization)] {
+        self.attribute_normalizations.as_slice()
+    }
+
+    /// Get whether to remove duplicate class names in `class` attributes.
+    #[inline]
+    pub const fn get_deduplicate_class_names(&self) -> bool {
+        self.deduplicate_class_names
+    }
+
+    /// Get whether to sort class names in `class` attributes.
+    #[inline]
+    pub const fn get_sort_class_names(&self) -> bool {
+        self.sort_class_names
+    }
+
+    /// Get whether to sort attributes by their names.
+    #[inline]
+    pub const fn get_sort_attributes(&self) -> bool {
+        self.sort_attributes
+    }
+}
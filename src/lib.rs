@@ -6,17 +6,29 @@ This library can help you generate and minify your HTML code at the same time. I
 HTML is minified by the following rules:
 
 * ASCII control characters (0x00-0x08, 0x11-0x1F, 0x7F) are always removed.
-* Comments can be optionally removed. (removed by default)
+* Comments can be optionally removed. (removed by default) IE conditional comments (e.g. `<!--[if IE]>...<![endif]-->`) and comments with specific prefixes (e.g. `<!--#include ... -->`) or accepted by a filter function can be kept.
 * **Useless** whitespaces (spaces, tabs and newlines) are removed.
-* Whitespaces (spaces, tabs and newlines) are converted to a single `'\x20'` or a single '\n', if possible.
+* Whitespaces (spaces, tabs and newlines) are converted to a single `'\x20'` or a single '\n', if possible. (configurable by `WhitespaceCollapse`)
+* Whitespaces around block elements (e.g. `<div>`, `<p>`, `<li>`) can be optionally removed. (kept by default)
+* End tags which can be omitted (e.g. `</li>`, `</p>`, `</td>`) can be optionally removed. (kept by default)
+* Start tags without attributes which can be omitted (e.g. `<html>`, `<head>`, `<body>`, `<tbody>`) can be optionally removed. (kept by default)
+* Whitespaces in `class` attributes are trimmed and collapsed. Attribute values such as `rel`, `srcset`, `href` and `method` can be optionally normalized by `HTMLMinifierOptions::html_attribute_normalizations` or `AttributeNormalization`. (kept by default)
 * Empty attribute values are collapsed. (e.g `<input readonly="">` => `<input readonly>` )
+* Boolean attributes can be optionally collapsed regardless of their values. (e.g `<input checked="checked">` => `<input checked>`, kept by default)
+* Attributes whose values equal the defaults of the elements can be optionally removed. (e.g `<form method="get">` => `<form>`, kept by default)
+* Empty attributes which have no effect can be optionally removed. (e.g `<div class="">` => `<div>`, kept by default)
+* Class names can be optionally deduplicated and sorted, and attributes can be optionally sorted for better compression. (kept by default)
+* Quotes around attribute values can be optionally removed where it is safe. (e.g `<div id="main">` => `<div id=main>`, kept by default)
 * The inner HTML of all elements is minified except for the following elements:
     * `<pre>`
     * `<textarea>`
     * `<code>` (optionally, minified by default)
+    * elements added by `HTMLMinifierOptions::add_raw_element` or carrying an attribute added by `HTMLMinifierOptions::add_raw_attribute` or matching a `WhitespacePreservingRule`
     * `<style>` (if the `type` attribute is unsupported)
-    * `<script>` (if the `type` attribute is unsupported)
-* JS code and CSS code in `<script>` and `<style>` elements are minified by [minifier](https://crates.io/crates/minifier).
+    * `<script>` (if the `type` attribute, or the `language` attribute (e.g. `vbscript`) when `type` is absent, is unsupported, i.e. not a JavaScript MIME type such as `text/javascript`, `module`, a JSON type or a type added by `HTMLMinifierOptions::add_javascript_type`)
+* JS code and CSS code in `<script>` and `<style>` elements are minified by [minifier](https://crates.io/crates/minifier). Other minifiers can be added for any MIME type (e.g. `text/babel`) by implementing `EmbeddedMinifier` and using `HTMLMinifierHelper::add_embedded_minifier`. If the code cannot be minified, the minification is aborted, or the code is kept (with a warning) and the minification continues. (configurable by `EmbeddedErrorPolicy`)
+* JSON data in `<script>` elements of JSON types (`application/json`, `application/ld+json`, `importmap` and `speculationrules`) is minified by removing whitespaces outside strings. (kept if it is not valid JSON)
+* CSS code in `style` attributes, JS code in event handler attributes (e.g. `onclick`) and `javascript:` URLs can be optionally minified. (kept by default)
 
 The original (non-minified) HTML doesn't need to be completely generated before using this library because this library doesn't do any deserialization to create DOMs.
 
@@ -84,9 +96,21 @@ html_minifier.digest("<script type='  application/javascript '>   alert('Hello!'
 assert_eq!("<script type='application/javascript'>alert('Hello!');</script>".as_bytes(), html_minifier.get_html());
 ```
 
+## Options
+
+The minification can be configured by `HTMLMinifierOptions`, which can be passed to `HTMLMinifier::with_options`, `HTMLMinifierHelper::with_options` and the `minify_with_options` function.
+
+```rust
+use html_minifier::{HTMLMinifierOptions, minify_with_options};
+
+let options = HTMLMinifierOptions::new().remove_comments(false);
+
+assert_eq!("<!-- Hello --> world!", minify_with_options("<!-- Hello -->   world!", &options).unwrap());
+```
+
 ## Write HTML to a Writer
 
-If you don't want to store your HTML in memory (e.g. writing to a file instead), you can use the `HTMLMinifierHelper` struct which provides a low-level API that allows you to pass your output instance when invoking the `digest` method.
+If you don't want to store your HTML in memory (e.g. writing to a file instead), you can use the `HTMLMinifierHelper` struct which provides a low-level API that allows you to pass your output instance when invoking the `digest` method. Invoke the `finish` method at the end of the input to write the buffered content. It returns `HTMLMinifierError::Unterminated` if the input ends inside a comment, a tag or a raw element (unless the `collect_diagnostics` option is enabled).
 
 ```rust,no_run
 use html_minifier::HTMLMinifierHelper;
@@ -110,16 +134,26 @@ loop {
 
     html_minifier_helper.digest(&buffer[..c], &mut output_file).unwrap();
 }
+
+html_minifier_helper.finish(&mut output_file).unwrap();
 ```
 */
+mod diagnostics;
+mod embedded_minifier;
 mod errors;
 mod functions;
 mod html_minifier_helper;
+mod html_minifier_options;
 mod html_writer;
 
+use std::sync::Arc;
+
+pub use diagnostics::*;
 use educe::Educe;
+pub use embedded_minifier::*;
 pub use errors::*;
 pub use html_minifier_helper::*;
+pub use html_minifier_options::*;
 pub use html_writer::*;
 
 use crate::functions::*;
@@ -134,28 +168,77 @@ pub struct HTMLMinifier {
 }
 
 impl HTMLMinifier {
+    /// Create a new `HTMLMinifier` instance with the given options.
+    #[inline]
+    pub fn with_options(options: HTMLMinifierOptions) -> Self {
+        Self {
+            helper: HTMLMinifierHelper::with_options(options),
+            ..Self::new()
+        }
+    }
+
+    /// Set the options.
+    #[inline]
+    pub fn set_options(&mut self, options: HTMLMinifierOptions) {
+        self.helper.set_options(options);
+    }
+
+    /// Add a minifier for the content of `script` and `style` elements of the MIME type. See `HTMLMinifierHelper::add_embedded_minifier`.
+    #[inline]
+    pub fn add_embedded_minifier<S: AsRef<str>>(
+        &mut self,
+        mime_type: S,
+        minifier: Arc<dyn EmbeddedMinifier>,
+    ) {
+        self.helper.add_embedded_minifier(mime_type, minifier);
+    }
+
+    /// Set a function to decide whether to keep a comment when comments are removed. See `HTMLMinifierHelper::set_comment_filter`.
+    #[inline]
+    pub fn set_comment_filter<F: Fn(&str) -> bool + Send + Sync + 'static>(&mut self, filter: F) {
+        self.helper.set_comment_filter(filter);
+    }
+
+    /// Get the warnings recorded with the `EmbeddedErrorPolicy::Warn` policy since the last reset.
+    #[inline]
+    pub fn get_warnings(&self) -> &[String] {
+        self.helper.get_warnings()
+    }
+
+    /// Get the diagnostics collected with the `collect_diagnostics` option since the last reset.
+    #[inline]
+    pub fn get_diagnostics(&self) -> &[Diagnostic] {
+        self.helper.get_diagnostics()
+    }
+
+    /// Get the options.
+    #[inline]
+    pub const fn get_options(&self) -> &HTMLMinifierOptions {
+        self.helper.get_options()
+    }
+
     /// Set whether to remove HTML comments.
     #[inline]
     pub fn set_remove_comments(&mut self, remove_comments: bool) {
-        self.helper.remove_comments = remove_comments;
+        self.helper.options.remove_comments = remove_comments;
     }
 
     /// Set whether to minify the content in the `code` element.
     #[inline]
     pub fn set_minify_code(&mut self, minify_code: bool) {
-        self.helper.minify_code = minify_code;
+        self.helper.options.minify_code = minify_code;
     }
 
     /// Get whether to remove HTML comments.
     #[inline]
     pub const fn get_remove_comments(&self) -> bool {
-        self.helper.remove_comments
+        self.helper.get_options().get_remove_comments()
     }
 
     /// Get whether to minify the content in the `code` element.
     #[inline]
     pub const fn get_minify_code(&self) -> bool {
-        self.helper.minify_code
+        self.helper.get_options().get_minify_code()
     }
 }
 
@@ -179,6 +262,12 @@ impl HTMLMinifier {
         self.helper.digest(text, &mut self.out)
     }
 
+    /// Signal the end of the input. See `HTMLMinifierHelper::finish`.
+    #[inline]
+    pub fn finish(&mut self) -> Result<(), HTMLMinifierError> {
+        self.helper.finish(&mut self.out)
+    }
+
     /// Directly input some text to generate HTML code. The text will just be appended to the output buffer instead of being through the helper.
     ///
     /// # When to Use This?
@@ -202,7 +291,16 @@ impl HTMLMinifier {
 /// Minify HTML.
 #[inline]
 pub fn minify<S: AsRef<str>>(html: S) -> Result<String, HTMLMinifierError> {
-    let mut minifier = HTMLMinifierHelper::new();
+    minify_with_options(html, &HTMLMinifierOptions::default())
+}
+
+/// Minify HTML with the given options. An unterminated comment, tag or element at the end of the input is not an error.
+#[inline]
+pub fn minify_with_options<S: AsRef<str>>(
+    html: S,
+    options: &HTMLMinifierOptions,
+) -> Result<String, HTMLMinifierError> {
+    let mut minifier = HTMLMinifierHelper::with_options(options.clone());
 
     let html = html.as_ref();
 
@@ -210,5 +308,11 @@ pub fn minify<S: AsRef<str>>(html: S) -> Result<String, HTMLMinifierError> {
 
     minifier.digest(html, unsafe { minified_html.as_mut_vec() })?;
 
+    // the output is complete even if the input is truncated
+    match minifier.finish(unsafe { minified_html.as_mut_vec() }) {
+        Ok(()) | Err(HTMLMinifierError::Unterminated(_)) => (),
+        Err(error) => return Err(error),
+    }
+
     Ok(minified_html)
 }
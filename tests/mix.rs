@@ -1,4 +1,9 @@
-use html_minifier::HTMLMinifier;
+use std::{error::Error, sync::Arc};
+
+use html_minifier::{
+    DiagnosticKind, EmbeddedCodeKind, EmbeddedMinifier, HTMLMinifier, HTMLMinifierError,
+    HTMLMinifierHelper, HTMLMinifierOptions, JavaScriptMinifier, minify_with_options,
+};
 
 #[test]
 fn reset() {
@@ -490,7 +495,7 @@ fn preserve_unsupported_script_type() {
     {
         html_minifier
             .digest(
-                r#"<script  type="  application/ecmascript  "  >
+                r#"<script  type="  text/plain  "  >
         alert('1234!')    ;
 
         </script >"#,
@@ -498,7 +503,7 @@ fn preserve_unsupported_script_type() {
             .unwrap();
 
         assert_eq!(
-            r#"<script type="application/ecmascript">
+            r#"<script type="text/plain">
         alert('1234!')    ;
 
         </script>"#
@@ -548,3 +553,235 @@ p  {
         );
     }
 }
+
+#[test]
+fn with_options() {
+    let options = HTMLMinifierOptions::new().remove_comments(false).minify_code(false);
+
+    let mut html_minifier = HTMLMinifier::with_options(options.clone());
+
+    assert_eq!(&options, html_minifier.get_options());
+
+    html_minifier.digest("<!-- x -->  <code>  1  </code>").unwrap();
+
+    assert_eq!(b"<!-- x --> <code>  1  </code>", html_minifier.get_html());
+
+    let mut out = Vec::new();
+
+    HTMLMinifierHelper::with_options(options.clone())
+        .digest("<!-- x -->  <code>  1  </code>", &mut out)
+        .unwrap();
+
+    assert_eq!(html_minifier.get_html(), out.as_slice());
+
+    assert_eq!(
+        "<!-- x --> <code>  1  </code>",
+        minify_with_options("<!-- x -->  <code>  1  </code>", &options).unwrap()
+    );
+
+    let mut html_minifier_helper = HTMLMinifierHelper::new();
+
+    html_minifier_helper.set_remove_comments(false);
+    html_minifier_helper.set_minify_code(false);
+
+    assert!(!html_minifier_helper.get_remove_comments());
+    assert!(!html_minifier_helper.get_minify_code());
+    assert_eq!(&options, html_minifier_helper.get_options());
+}
+
+#[test]
+fn embedded_minifiers() {
+    struct Identity;
+
+    impl EmbeddedMinifier for Identity {
+        fn minify(
+            &self,
+            kind: EmbeddedCodeKind,
+            source: &str,
+            out: &mut String,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            out.push_str(match kind {
+                EmbeddedCodeKind::Script => "/*script*/",
+                EmbeddedCodeKind::Style => "/*style*/",
+            });
+            out.push_str(source);
+
+            Ok(())
+        }
+    }
+
+    let mut html_minifier = HTMLMinifier::new();
+
+    html_minifier.add_embedded_minifier("text/javascript", Arc::new(Identity));
+    html_minifier.add_embedded_minifier("Text/CSS; charset=utf-8", Arc::new(Identity));
+    html_minifier.add_embedded_minifier("text/babel", Arc::new(JavaScriptMinifier));
+
+    html_minifier
+        .digest(
+            "<script> f( 1 ) </script><script type=module> f( 1 ) </script><style> a { } </style>",
+        )
+        .unwrap();
+    html_minifier.digest("<script type='text/babel'> f( 1 ) </script>").unwrap();
+    html_minifier.digest("<script type='text/x-template'> f( 1 ) </script>").unwrap();
+
+    assert_eq!(
+        "<script>/*script*/ f( 1 ) </script><script type=module>f(1)</script><style>/*style*/ a { \
+         } </style><script type='text/babel'>f(1)</script><script type='text/x-template'> f( 1 ) \
+         </script>"
+            .as_bytes(),
+        html_minifier.get_html()
+    );
+
+    let mut out = Vec::new();
+
+    let mut html_minifier_helper = HTMLMinifierHelper::new();
+
+    html_minifier_helper.add_embedded_minifier("text/x-template", Arc::new(Identity));
+
+    for c in "<script type='text/x-template'> f( 1 ) </script >".chars() {
+        html_minifier_helper.digest(c.to_string(), &mut out).unwrap();
+    }
+
+    assert_eq!(b"<script type='text/x-template'>/*script*/ f( 1 ) </script>", out.as_slice());
+}
+
+#[test]
+fn embedded_minifier_error() {
+    struct Reject;
+
+    impl EmbeddedMinifier for Reject {
+        fn minify(
+            &self,
+            _kind: EmbeddedCodeKind,
+            _source: &str,
+            _out: &mut String,
+        ) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Err("unsupported syntax".into())
+        }
+    }
+
+    let mut html_minifier = HTMLMinifier::new();
+
+    html_minifier.add_embedded_minifier("text/x-template", Arc::new(Reject));
+
+    let error = html_minifier.digest("<script type=text/x-template> f( 1 ) </script>").unwrap_err();
+
+    assert_eq!(
+        "failed to minify the content of the `script` element at line 1, column 1: unsupported \
+         syntax",
+        error.to_string()
+    );
+}
+
+#[test]
+fn embedded_code_error() {
+    const INPUT: &str = "<p>中文</p>\n  <div>\r\n    <style>\n      a { b: 'c }\n    </style>";
+
+    let mut html_minifier = HTMLMinifier::new();
+
+    for c in INPUT.chars() {
+        match html_minifier.digest(c.to_string()) {
+            Ok(()) => (),
+            Err(HTMLMinifierError::EmbeddedCodeError {
+                position,
+                tag,
+                message,
+            }) => {
+                assert_eq!(INPUT.find("<style>").unwrap(), position.offset);
+                assert_eq!(3, position.line);
+                assert_eq!(5, position.column);
+                assert_eq!("style", tag);
+                assert_eq!("Unclosed string", message);
+
+                return;
+            },
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    panic!("no error");
+}
+
+#[test]
+fn finish() {
+    let options = HTMLMinifierOptions::new()
+        .remove_optional_start_tags(true)
+        .remove_optional_end_tags(true)
+        .collect_diagnostics(true);
+
+    const CASES: [(&str, &str, Option<DiagnosticKind>); 9] = [
+        ("<script>f(1)", "<script> f( 1 ) ", Some(DiagnosticKind::UnclosedElement)),
+        ("<style>a{b:c}</sty", "<style> a { b: c } </sty", Some(DiagnosticKind::UnclosedElement)),
+        ("<pre> a ", "<pre> a ", Some(DiagnosticKind::UnclosedElement)),
+        ("", "<!-- a ", Some(DiagnosticKind::UnterminatedComment)),
+        ("a <", "a <", Some(DiagnosticKind::UnterminatedTag)),
+        ("<p>a</p> <b", "<p>a</p> <b", Some(DiagnosticKind::UnterminatedTag)),
+        ("<table><tbody>", "<table><tbody>", None),
+        ("", "<html><head></head><body>", None),
+        ("<ul><li>a</ul>", "<ul><li>a</li></ul>", None),
+    ];
+
+    for (expect, input, diagnostic) in CASES {
+        let mut html_minifier = HTMLMinifier::with_options(options.clone());
+
+        for c in input.chars() {
+            html_minifier.digest(c.to_string()).unwrap();
+        }
+
+        html_minifier.finish().unwrap();
+        html_minifier.finish().unwrap();
+
+        assert_eq!(expect.as_bytes(), html_minifier.get_html(), "{input}");
+        assert_eq!(diagnostic, html_minifier.get_diagnostics().first().map(|d| d.kind), "{input}");
+    }
+
+    assert_eq!("<script>f(1)", minify_with_options("<script> f( 1 ) ", &options).unwrap());
+
+    // the attributes of an unterminated start tag are written as they are
+    const CASES2: [(&str, &str); 5] = [
+        ("x <a id=1 title=y", "x <a id=1 title=y"),
+        ("<a b=\"c\" style=\"color: red", "<a b=\"c\" style=\"color: red"),
+        ("<a title='x y", "<a title='x y"),
+        ("<a id=", "<a id="),
+        ("<a hidden", "<a hidden"),
+    ];
+
+    for options in [
+        HTMLMinifierOptions::new(),
+        HTMLMinifierOptions::new().remove_attribute_quotes(true).sort_attributes(true),
+        HTMLMinifierOptions::new().minify_style_attributes(true).html_attribute_normalizations(),
+    ] {
+        for (expect, input) in CASES2 {
+            let mut html_minifier = HTMLMinifier::with_options(options.clone());
+
+            for c in input.chars() {
+                html_minifier.digest(c.to_string()).unwrap();
+            }
+
+            assert!(html_minifier.finish().is_err());
+
+            assert_eq!(expect, String::from_utf8_lossy(html_minifier.get_html()), "{options:?}");
+        }
+    }
+
+    // without diagnostics, the unterminated construct is returned as an error after the output is written
+    let mut html_minifier = HTMLMinifier::new();
+
+    html_minifier.digest("a <!-- b").unwrap();
+
+    match html_minifier.finish() {
+        Err(HTMLMinifierError::Unterminated(diagnostic)) => {
+            assert_eq!(DiagnosticKind::UnterminatedComment, diagnostic.kind);
+            assert_eq!(2, diagnostic.position.offset);
+        },
+        result => panic!("{result:?}"),
+    }
+
+    html_minifier.finish().unwrap();
+
+    assert_eq!(b"a ", html_minifier.get_html());
+    assert_eq!(
+        "a <script>f(1)",
+        minify_with_options("a <script> f( 1 ) ", &HTMLMinifierOptions::new()).unwrap()
+    );
+}
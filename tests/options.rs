@@ -0,0 +1,694 @@
+use std::str::from_utf8_unchecked;
+
+use html_minifier::{
+    AttributeNormalization, DiagnosticKind, EmbeddedErrorPolicy, HTMLMinifier, HTMLMinifierOptions,
+    WhitespaceCollapse, WhitespacePreservingRule,
+};
+
+fn test_with_options(options: &HTMLMinifierOptions, cases: &[(&str, &str)]) {
+    for (index, (expect, test)) in cases.iter().copied().enumerate() {
+        let mut html_minifier = HTMLMinifier::with_options(options.clone());
+        html_minifier.digest(test).unwrap();
+        assert_eq!(expect.as_bytes(), html_minifier.get_html(), "case {}", index);
+    }
+
+    let mut buffer = [0u8; 8];
+
+    for (index, (expect, test)) in cases.iter().copied().enumerate() {
+        let mut html_minifier = HTMLMinifier::with_options(options.clone());
+
+        for c in test.chars() {
+            html_minifier.digest(c.encode_utf8(&mut buffer)).unwrap();
+        }
+
+        assert_eq!(expect.as_bytes(), html_minifier.get_html(), "case-chunk-1 {}", index);
+    }
+
+    for (index, (expect, test)) in cases.iter().copied().enumerate() {
+        let mut html_minifier = HTMLMinifier::with_options(options.clone());
+
+        let mut chars = test.chars();
+
+        while let Some(c) = chars.next() {
+            let mut length = c.encode_utf8(&mut buffer).len();
+
+            if let Some(c) = chars.next() {
+                length = length + c.encode_utf8(&mut buffer[length..]).len();
+            }
+
+            html_minifier.digest(unsafe { from_utf8_unchecked(&buffer[..length]) }).unwrap();
+        }
+
+        assert_eq!(expect.as_bytes(), html_minifier.get_html(), "case-chunk-2 {}", index);
+    }
+}
+
+#[test]
+fn raw_elements() {
+    let options = HTMLMinifierOptions::new().add_raw_element("xmp").add_raw_element("X-Snippet");
+
+    const CASES: [(&str, &str); 5] = [
+        ("<xmp>  <b>  1  </b>  </XMP>", "<xmp  >  <b>  1  </b>  </XMP >"),
+        ("<x-snippet a=1>  1  </x-snippet>", "<x-snippet a=1>  1  </x-snippet>"),
+        ("<pre class=x>  1  </pre>", "<pre class=x>  1  </pre>"),
+        ("<pre>  1  </pre> <div> 1 </div>", "<pre>  1  </pre>  <div>  1  </div>"),
+        ("<div> 1 </div>", "<div>  1  </div>"),
+    ];
+
+    test_with_options(&options, &CASES);
+
+    let options = HTMLMinifierOptions::new().raw_elements(["div"]);
+
+    const CASES2: [(&str, &str); 3] = [
+        ("<div>  <div>  1  </div>  </div> 2", "<div>  <div>  1  </div>  </div>  2"),
+        ("<div>  <divx>  </div> 2", "<div>  <divx>  </div>  2"),
+        ("<pre> 1 </pre>", "<pre>  1  </pre>"),
+    ];
+
+    test_with_options(&options, &CASES2);
+}
+
+#[test]
+fn raw_attributes() {
+    let options = HTMLMinifierOptions::new().add_raw_attribute("data-no-minify");
+
+    const CASES: [(&str, &str); 6] = [
+        (
+            "<div data-no-minify>  <div>  1  </div>  </div> 2",
+            "<div data-no-minify>  <div>  1  </div>  </div>  2",
+        ),
+        (
+            "<span class=a DATA-NO-MINIFY>  1  </span> 2",
+            "<span class=a DATA-NO-MINIFY=''>  1  </span>  2",
+        ),
+        ("<script data-no-minify>  a  ;  </script>", "<script data-no-minify>  a  ;  </script>"),
+        ("<div data-minify> 1 </div>", "<div data-minify>  1  </div>"),
+        ("<img data-no-minify src=x> a <p> b </p>", "<img data-no-minify src=x>  a  <p>  b  </p>"),
+        ("<p data-no-minify> a <p> b </p>", "<p data-no-minify>  a  <p>  b  </p>"),
+    ];
+
+    test_with_options(&options, &CASES);
+}
+
+#[test]
+fn whitespace_preserving_rules() {
+    let options = HTMLMinifierOptions::new()
+        .add_whitespace_preserving_rule(WhitespacePreservingRule::class("highlight"))
+        .add_whitespace_preserving_rule(WhitespacePreservingRule::attribute(
+            "style",
+            "white-space:pre",
+        ));
+
+    const CASES: [(&str, &str); 9] = [
+        (
+            "<div class='a highlight'>  <div>  1  </div>  </div> 2",
+            "<div   class='  a   highlight '>  <div>  1  </div>  </div>  2",
+        ),
+        ("<span class=highlight>  1  </span> 2", "<span class=highlight>  1  </span>  2"),
+        ("<div class='highlighted'> 1 </div>", "<div class='highlighted'>  1  </div>"),
+        (
+            "<span style='color: red; White-Space: pre-wrap'>  <span>1</span>  </span> 2",
+            "<span style='color: red; White-Space: pre-wrap'>  <span>1</span>  </span>  2",
+        ),
+        (
+            "<span style=\"white-space&#58; pre\">  1  </span>",
+            "<span style=\"white-space&#58; pre\">  1  </span>",
+        ),
+        (
+            "<span style='white-space: normal'> 1 </span>",
+            "<span style='white-space: normal'>  1  </span>",
+        ),
+        // void elements and elements with optional end tags are not preserved
+        (
+            "<input class=\"highlight\"> a b <p> c </p>",
+            "<input class=\"highlight\">  a   b  <p>  c  </p>",
+        ),
+        ("<ul><li class=highlight>a<li>b</ul> c", "<ul><li class=highlight>a<li>b</ul>  c"),
+        ("<p class=highlight> a <div> b </div>", "<p class=highlight>  a  <div>  b  </div>"),
+    ];
+
+    test_with_options(&options, &CASES);
+}
+
+#[test]
+fn aggressive_whitespace() {
+    let options = HTMLMinifierOptions::new().aggressive_whitespace(true);
+
+    const CASES: [(&str, &str); 9] = [
+        ("<div><p>a <b>b</b> c</p></div>text", "<div>\n  <p>a  <b>b</b>  c </p>\n</div>\n text"),
+        ("<ul><li>1</li><li>2</li></ul>", "<ul>\n  <li> 1 </li>\n  <li>2</li>\n</ul>"),
+        ("<table><tr><td>1</td></tr></table>", "<table>  <tr>  <td> 1 </td>  </tr>  </table>"),
+        ("a <span>b</span> c", "a  <span>b</span>  c"),
+        ("a\n<SPAN>b</Span>\nc", "a\n<SPAN>b</Span>\nc"),
+        ("<P>a</DIV>b", "<P>  a  </DIV >  b"),
+        ("<html><head><title>x</title></head>", "<html>\n<head>\n  <title>x</title>\n</head>"),
+        ("a <!-- b -->c", "a <!-- b -->  c"),
+        ("a <é </é", "a  <é  </é"),
+    ];
+
+    test_with_options(&options.clone().remove_comments(false), &CASES);
+}
+
+#[test]
+fn whitespace_collapse() {
+    let options = HTMLMinifierOptions::new().whitespace_collapse(WhitespaceCollapse::Space);
+
+    const CASES: [(&str, &str); 3] = [
+        ("<div> 1 </div> <div> 2 3 </div>", "<div>\n 1 \n</div>\n\n<div> 2\n3 </div>"),
+        ("a <b>b</b> é", "a \n <b>b</b>\n\n é"),
+        ("a", "\n\na\n\n"),
+    ];
+
+    test_with_options(&options, &CASES);
+
+    test_with_options(&options.aggressive_whitespace(true), &[(
+        "<div>1</div><div>2 3</div>",
+        "<div>\n 1 \n</div>\n\n<div> 2\n3 </div>",
+    )]);
+
+    let options =
+        HTMLMinifierOptions::new().whitespace_collapse(WhitespaceCollapse::PreserveLineBreaks);
+
+    const CASES2: [(&str, &str); 5] = [
+        ("<div>\n1\n</div>\n\n<div> 2\n3 </div>", "<div>\n 1 \n</div>\n\n<div> 2\n3 </div>"),
+        ("a\n<b>b</b>\n\né", "a \n <b>b</b>\n \n é"),
+        ("a\n\n\nb", "a\n\n\n  b"),
+        // the whitespaces around a removed comment are collapsed as one run
+        ("a\nb", "a\n<!-- x -->\nb"),
+        ("a\n\nb", "a\n\n<!-- x\ny -->\n\nb"),
+    ];
+
+    test_with_options(&options, &CASES2);
+}
+
+#[test]
+fn remove_optional_end_tags() {
+    let options = HTMLMinifierOptions::new().remove_optional_end_tags(true);
+
+    const CASES: [(&str, &str); 10] = [
+        ("<ul><li>1<li>2</ul>", "<ul><li>1</li><li>2</li></ul>"),
+        ("<ul><li>1</li>\n<li>2</ul>", "<ul><li>1</li>\n<li>2</li></ul>"),
+        (
+            "<table><tr><td>1<td>2<tr><th>3</table>",
+            "<table><tr><td>1</td><td>2</td></tr><tr><th>3</th></tr></table>",
+        ),
+        (
+            "<p>1<p>2<div>3</div><p>4</p><span>5</span>",
+            "<p>1</p><p>2</p><div>3</div><p>4</p><span>5</span>",
+        ),
+        ("<a><p>1</p></a>", "<a><p>1</p></a>"),
+        ("<x-foo><p>x</p></x-foo>", "<x-foo><p>x</p></x-foo>"),
+        (
+            "<select><option>1<option>2</select>",
+            "<select><option>1</option ><option>2</option></select>",
+        ),
+        (
+            "<html><head><title>x</title></head>\n<body>1",
+            "<html><head><title>x</title></head>\n<body>1</body></html>",
+        ),
+        ("<p>1</p>2", "<p>1</p>2"),
+        ("<p>1</p><!--2-->", "<p>1</p><!--2--></html>"),
+    ];
+
+    test_with_options(&options.clone().remove_comments(false), &CASES);
+
+    test_with_options(&options.aggressive_whitespace(true), &[(
+        "<ul><li>1<li>2</ul><p>3",
+        "<ul>\n  <li> 1 </li>\n  <li>2</li>\n</ul>\n<p>3</p>",
+    )]);
+}
+
+#[test]
+fn remove_optional_start_tags() {
+    let options = HTMLMinifierOptions::new().remove_optional_start_tags(true);
+
+    const CASES: [(&str, &str); 10] = [
+        (
+            "<title>x</title></head><p>1</p></body></html>",
+            "<html><head><title>x</title></head><body><p>1</p></body></html>",
+        ),
+        ("<html lang=en><body class=a>1", "<html lang=en><body class=a>1"),
+        (
+            "<!DOCTYPE html><html><!--c--><body><!--d-->",
+            "<!DOCTYPE html><html><!--c--><body><!--d-->",
+        ),
+        ("<body><script>1</script>", "<body><script>1</script>"),
+        ("<body>\n<p>1", "<body>\n<p>1"),
+        ("<table><col><tr><td>1</table>", "<table><colgroup><col><tbody><tr><td>1</table>"),
+        (
+            "<table><tr><td>1</tbody><tr><td>2</table>",
+            "<table><tbody><tr><td>1</tbody><tbody><tr><td>2</table>",
+        ),
+        ("<table><tbody/><tbody id=a><tr>", "<table><tbody/><tbody id=a><tr>"),
+        (
+            "<table><tr><td>1</td></tr><tbody><tr><td>2</td></tr></tbody></table>",
+            "<table><tr><td>1</td></tr><tbody><tr><td>2</td></tr></tbody></table>",
+        ),
+        ("<table><tr><td>1<tbody><tr><td>2</table>", "<table><tr><td>1<tbody><tr><td>2</table>"),
+    ];
+
+    test_with_options(&options.clone().remove_comments(false), &CASES);
+
+    let options = options.remove_optional_end_tags(true);
+
+    test_with_options(&options, &[(
+        "<table><tr><td>1<tbody><tr><td>2</table>",
+        "<table><tbody><tr><td>1</tbody><tbody><tr><td>2</table>",
+    )]);
+
+    test_with_options(&options.aggressive_whitespace(true), &[(
+        "<!DOCTYPE html><meta charset=utf-8><title>x</title><p>1",
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta \
+         charset=utf-8>\n<title>x</title>\n</head>\n<body>\n<p>1</p>\n</body>\n</html>\n",
+    )]);
+}
+
+#[test]
+fn remove_attribute_quotes() {
+    let options = HTMLMinifierOptions::new().remove_attribute_quotes(true);
+
+    const CASES: [(&str, &str); 11] = [
+        ("<div id=main class=\"a b\">", "<div id=\"main\" class=\" a  b \">"),
+        ("<a href=/x/ title=x>", "<a href='/x/' title=\"x\">"),
+        ("<a href=/x>", "<a href=/x>"),
+        ("<input value=\"a=b\" data-x=\"`\">", "<input value='a=b' data-x='`'>"),
+        ("<p title='a \"b\"'>", "<p title='a \"b\"'>"),
+        ("<p title=\"a 'b'\">", "<p title=\"a 'b'\">"),
+        ("<p title='\"b\" &#39;'>", "<p title=\"&quot;b&quot; '\">"),
+        ("<p title=&APOS;x>", "<p title='&APOS;x'>"),
+        ("<p title=&quot;b>", "<p title=\"&quot;b\">"),
+        ("<p hidden>", "<p hidden=\"\">"),
+        ("<br class=a />", "<br class=\"a\"/>"),
+    ];
+
+    test_with_options(&options, &CASES);
+}
+
+#[test]
+fn collapse_boolean_attributes() {
+    let options = HTMLMinifierOptions::new().collapse_boolean_attributes(true);
+
+    const CASES: [(&str, &str); 7] = [
+        ("<input checked disabled>", "<input checked=\"checked\" disabled='disabled'>"),
+        ("<option selected value=\"1\">", "<option selected=true value=\"1\">"),
+        (
+            "<script async defer src=\"a.js\"></script>",
+            "<script async=\"\" defer=defer src=\"a.js\"></script>",
+        ),
+        ("<p hidden>", "<p hidden=\"hidden\">"),
+        ("<p hidden=until-found>", "<p hidden=\"Until-Found\">"),
+        ("<input required/>", "<input required=\"required\" />"),
+        ("<x-a checked=\"no\">", "<x-a checked=\"no\">"),
+    ];
+
+    test_with_options(&options, &CASES);
+
+    test_with_options(&options.remove_attribute_quotes(true), &[(
+        "<input checked value=a>",
+        "<input checked=\"checked\" value=\"a\">",
+    )]);
+}
+
+#[test]
+fn remove_redundant_attributes() {
+    let options = HTMLMinifierOptions::new().remove_redundant_attributes(true);
+
+    const CASES: [(&str, &str); 10] = [
+        ("<script>var a;</script>", "<script type=\"text/javascript\">var a;</script>"),
+        (
+            "<script type=\"text/javascript\" language=\"vbscript\">a</script>",
+            "<script type=\"text/javascript\" language=\"vbscript\">a</script>",
+        ),
+        ("<style>a{}</style>", "<style TYPE='Text/CSS'>a{}</style>"),
+        ("<form action=\"/\">", "<form method=\"get\" action=\"/\">"),
+        ("<form method=\"post\">", "<form method=\"post\">"),
+        (
+            "<input NAME='a' value=\"b\" hidden>",
+            "<input type=\"text\" NAME='a' value=\"b\" hidden>",
+        ),
+        (
+            "<link href=\"a.css\" rel=\"stylesheet\">",
+            "<link type=\"text/css\" href=\"a.css\" rel=\"stylesheet\">",
+        ),
+        (
+            "<link rel=\"preload\" type=\"text/css\" href=\"a.css\">",
+            "<link rel=\"preload\" type=\"text/css\" href=\"a.css\">",
+        ),
+        ("<td>1<td rowspan=2>2", "<td colspan=\"1\">1<td rowspan=2>2"),
+        ("<button/>", "<button type=submit />"),
+    ];
+
+    test_with_options(&options, &CASES);
+
+    test_with_options(&options.remove_optional_start_tags(true).remove_attribute_quotes(true), &[
+        ("<table class=a><col></table>", "<table class=\"a\"><colgroup span=\"1\"><col></table>"),
+    ]);
+}
+
+#[test]
+fn remove_empty_attributes() {
+    let options = HTMLMinifierOptions::new().remove_empty_attributes(true);
+
+    const CASES: [(&str, &str); 8] = [
+        ("<div>", "<div class=\"\" id='' style=\"\">"),
+        ("<div>", "<div class=\"  \" OnClick=\"\" title>"),
+        ("<img alt src=\"a.png\">", "<img alt=\"\" src=\"a.png\" lang=\"\">"),
+        ("<div class=\"a\">", "<div class=\" a \" style=\" \">"),
+        ("<div title=\" \">", "<div id=\"  \" style=\"\n\" onclick=\" \" title=\" \">"),
+        ("<input value/>", "<input value=\"\" dir=\"\" />"),
+        ("<p>1", "<html lang=\"\"><p>1"),
+        ("<x-a only=\" \" onfoo=\" \">", "<x-a only=\" \" onblur=\" \" onfoo=\" \">"),
+    ];
+
+    test_with_options(&options.clone().remove_optional_start_tags(true), &CASES);
+
+    test_with_options(&options.empty_attributes(["alt", "data-*"]), &[(
+        "<img class src=\"a.png\">",
+        "<img class alt=\"\" src=\"a.png\" data-a=\"\" DATA-B>",
+    )]);
+}
+
+#[test]
+fn class_names_and_attributes_sorting() {
+    test_with_options(&HTMLMinifierOptions::new().deduplicate_class_names(true), &[
+        ("<div class=\"b a c\">", "<div class=\" b a b  c a \">"),
+        ("<div class=a>", "<div class=a>"),
+    ]);
+
+    test_with_options(&HTMLMinifierOptions::new().sort_class_names(true), &[(
+        "<div class=\"a b b c\">",
+        "<div class=\"c b a b\">",
+    )]);
+
+    test_with_options(
+        &HTMLMinifierOptions::new().deduplicate_class_names(true).sort_class_names(true),
+        &[("<div class=\"a b c\">", "<div class=\"c b a b\">")],
+    );
+
+    test_with_options(&HTMLMinifierOptions::new().sort_attributes(true), &[
+        ("<a class=\"x\" HREF=\"/\" id=a title='t'>", "<a title='t' id=a HREF=\"/\" class=\"x\">"),
+        ("<a href=\"/a\" href=\"/b\" id=a>", "<a id=a href=\"/a\" href=\"/b\">"),
+    ]);
+}
+
+#[test]
+fn attribute_normalizations() {
+    const CASES: [(&str, &str); 7] = [
+        ("<a rel=\"noopener noreferrer\">", "<a rel=\"  noopener \n  noreferrer \">"),
+        (
+            "<img srcset=\"a.png 1x, b.png 2x\" sizes='(min-width: 1px) 1px, 2px'>",
+            "<img srcset=\"\n  a.png 1x ,\n  b.png 2x\n\" sizes=' (min-width:  1px) 1px , 2px '>",
+        ),
+        ("<a href=\"/a b\">", "<a href=\" /a b \n\">"),
+        ("<a href>", "<a href=\"  \">"),
+        ("<form method=\"post\" dir=rtl>", "<form method=\" POST \" dir=RTL>"),
+        ("<input accept=\".png, .jpg\">", "<input accept=\".png , .jpg\">"),
+        ("<p title=\" a  b \">", "<p title=\" a  b \">"),
+    ];
+
+    let options = HTMLMinifierOptions::new().html_attribute_normalizations();
+
+    test_with_options(&options, &CASES);
+    test_with_options(&options.collapse_boolean_attributes(true), &CASES);
+
+    // only `class` is normalized by default
+    test_with_options(&HTMLMinifierOptions::new(), &[(
+        "<a class=\"a b\" rel=\"  noopener \n  noreferrer \" href=\" /a \">",
+        "<a class=\" a  b \" rel=\"  noopener \n  noreferrer \" href=\" /a \">",
+    )]);
+
+    let options = HTMLMinifierOptions::new()
+        .add_attribute_normalization("data-tags", AttributeNormalization::TokenList)
+        .add_attribute_normalization("DIR", AttributeNormalization::TokenList);
+
+    test_with_options(&options, &[(
+        "<p data-tags=\"a b\" dir=\"RTL\">",
+        "<p data-tags=\" a  b\" dir=\" RTL \">",
+    )]);
+
+    test_with_options(
+        &HTMLMinifierOptions::new()
+            .attribute_normalizations([("title", AttributeNormalization::CaseInsensitiveKeyword)]),
+        &[("<a rel=\" a  b \" title=\"x\">", "<a rel=\" a  b \" title=\" X \">")],
+    );
+}
+
+#[test]
+fn minify_style_attributes() {
+    let options = HTMLMinifierOptions::new().minify_style_attributes(true);
+
+    const CASES: [(&str, &str); 6] = [
+        (
+            "<p style=\"color:red;margin:0 auto\">",
+            "<p style=\" color : red ; margin : 0  auto; \">",
+        ),
+        ("<p style='font-family:\"a b\"'>", "<p style='font-family: \"a b\" ;'>"),
+        ("<p style=\"font-family:&#34;a b&#34;\">", "<p style=\"font-family: &quot;a b&quot;\">"),
+        ("<p style=\"content:'&amp;'\">", "<p style=\"content: '&amp;' ;\">"),
+        ("<p style=color:red>", "<p style=color:red;>"),
+        ("<p style=\"a}}b\">", "<p style=\"a}}b\">"),
+    ];
+
+    test_with_options(&options, &CASES);
+
+    test_with_options(&options.remove_attribute_quotes(true), &[(
+        "<p style='font-family:\"a b\"' class=a>",
+        "<p style=\"font-family: &quot;a b&quot;\" class=\"a\">",
+    )]);
+}
+
+#[test]
+fn minify_event_handlers() {
+    let options = HTMLMinifierOptions::new().minify_event_handlers(true);
+
+    const CASES: [(&str, &str); 6] = [
+        (
+            "<a onclick=\"alert('a');return false;\">",
+            "<a onclick=\" alert( 'a' ); return false; \">",
+        ),
+        ("<body OnLoad='init(\"a b\")'>", "<body OnLoad='init ( \"a b\" )'>"),
+        ("<a onclick=\"f(&#34;a&#34;)\">", "<a onclick=\"f( &quot;a&quot; )\">"),
+        ("<a onclick=\"a&amp;&amp;b()\">", "<a onclick=\"a &amp;&amp; b()\">"),
+        ("<a on=\" a \">", "<a on=\" a \">"),
+        ("<x-a only=\" a  b \" one=\" a \">", "<x-a only=\" a  b \" one=\" a \">"),
+    ];
+
+    test_with_options(&options, &CASES);
+
+    test_with_options(&options.remove_attribute_quotes(true), &[(
+        "<a onclick='f(\"a\")' onblur=f()>",
+        "<a onclick=\"f( &quot;a&quot; )\" onblur=\"f( )\">",
+    )]);
+}
+
+#[test]
+fn minify_javascript_urls() {
+    let options = HTMLMinifierOptions::new().minify_javascript_urls(true);
+
+    const CASES: [(&str, &str); 5] = [
+        ("<a href=\"javascript:void(0);\">", "<a href=\" javascript: void( 0 ); \">"),
+        ("<a href=\"javascript:f('a b')\">", "<a href=\"JavaScript: f ( 'a b' )\">"),
+        ("<form action=\"javascript:x%20= 1\">", "<form action=\"javascript:x%20= 1\">"),
+        ("<a href=\" /a b\">", "<a href=\" /a b\">"),
+        ("<a title=\"javascript: a\">", "<a title=\"javascript: a\">"),
+    ];
+
+    test_with_options(&options, &CASES);
+}
+
+#[test]
+fn javascript_types() {
+    const CASES: [(&str, &str); 5] = [
+        (
+            "<script type='text/javascript; charset=utf-8'>f(1);</script>",
+            "<script type='text/javascript; charset=utf-8'> f( 1 ) ; </script>",
+        ),
+        (
+            "<script type='Text/JScript'>f(1);</script>",
+            "<script type='Text/JScript'> f( 1 ) ; </script>",
+        ),
+        (
+            "<script type=application/x-javascript>f(1);</script>",
+            "<script type=application/x-javascript> f( 1 ) ; </script>",
+        ),
+        (
+            "<script type=text/javascript1.5>f(1);</script>",
+            "<script type=text/javascript1.5> f( 1 ) ; </script>",
+        ),
+        (
+            "<script type=text/babel> f( 1 ) ; </script>",
+            "<script type=text/babel> f( 1 ) ; </script>",
+        ),
+    ];
+
+    test_with_options(&HTMLMinifierOptions::new(), &CASES);
+
+    let options = HTMLMinifierOptions::new().add_javascript_type("Text/Babel; preset=env");
+
+    assert_eq!(&["text/babel"], options.get_javascript_types());
+
+    test_with_options(&options, &[(
+        "<script type='text/babel;x=y'>f(1);</script>",
+        "<script type='text/babel;x=y'> f( 1 ) ; </script>",
+    )]);
+}
+
+#[test]
+fn embedded_error_policy() {
+    const INPUT: &str = "<style> a { b: 'c } </style> <style> a { b: c } </style>";
+
+    let mut html_minifier = HTMLMinifier::new();
+
+    assert!(html_minifier.digest(INPUT).is_err());
+
+    let cases = [("<style> a { b: 'c } </style> <style>a{b:c}</style>", INPUT)];
+
+    test_with_options(
+        &HTMLMinifierOptions::new().embedded_error_policy(EmbeddedErrorPolicy::Verbatim),
+        &cases,
+    );
+
+    let options = HTMLMinifierOptions::new().embedded_error_policy(EmbeddedErrorPolicy::Warn);
+
+    test_with_options(&options, &cases);
+
+    let mut html_minifier = HTMLMinifier::with_options(options);
+
+    html_minifier.digest(INPUT).unwrap();
+
+    assert_eq!(
+        &["failed to minify the content of the `style` element at line 1, column 1: Unclosed \
+           string"],
+        html_minifier.get_warnings()
+    );
+
+    html_minifier.reset();
+
+    assert!(html_minifier.get_warnings().is_empty());
+}
+
+#[test]
+fn collect_diagnostics() {
+    const INPUT: &str = "<a id=x href='/' ID=y>1</a>\n<p title=>2</ p>\n</>\n<a id=x>中<a href=/>";
+
+    let options = HTMLMinifierOptions::new().collect_diagnostics(true);
+
+    test_with_options(&options, &[(
+        "<a id=x href='/' ID=y>1</a>\n<p title>2</ p>\n\n<a id=x>中<a href=/>",
+        INPUT,
+    )]);
+
+    let expect = [
+        (DiagnosticKind::DuplicateAttribute, 17, 1, 18, "duplicate attribute `id`"),
+        (DiagnosticKind::MissingAttributeValue, 31, 2, 4, "missing value of the attribute `title`"),
+        (DiagnosticKind::StrayEndTagOpen, 39, 2, 12, "`</` is not followed by a tag name"),
+        (DiagnosticKind::StrayEndTagOpen, 45, 3, 1, "`</` is not followed by a tag name"),
+    ];
+
+    for chunk_size in [INPUT.len(), 1, 2] {
+        let mut html_minifier = HTMLMinifier::with_options(options.clone());
+
+        for chunk in INPUT.as_bytes().chunks(chunk_size) {
+            html_minifier.digest(chunk).unwrap();
+        }
+
+        let diagnostics: Vec<_> = html_minifier
+            .get_diagnostics()
+            .iter()
+            .map(|d| {
+                (d.kind, d.position.offset, d.position.line, d.position.column, d.message.as_str())
+            })
+            .collect();
+
+        assert_eq!(&expect[..], diagnostics.as_slice());
+    }
+
+    let mut html_minifier = HTMLMinifier::new();
+
+    html_minifier.digest(INPUT).unwrap();
+
+    assert!(html_minifier.get_diagnostics().is_empty());
+}
+
+#[test]
+fn comment_filters() {
+    const INPUT: &str = "<!--[if IE]>  <p class='a' >  IE  </p>  <![endif]--><!--[if !IE]><!--> a \
+                         <!--<![endif]--><!--#include file=\"a\" --> <!-- ko if: x --><!-- /ko \
+                         --><!--! MIT --><!-- x -->";
+
+    test_with_options(&HTMLMinifierOptions::new(), &[(" a  ", INPUT)]);
+
+    let options = HTMLMinifierOptions::new().keep_conditional_comments(true);
+
+    test_with_options(&options, &[(
+        "<!--[if IE]>  <p class='a' >  IE  </p>  <![endif]--><!--[if !IE]><!--> a \
+         <!--<![endif]--> ",
+        INPUT,
+    )]);
+
+    let options = options
+        .minify_conditional_comments(true)
+        .add_preserved_comment_prefix("#")
+        .add_preserved_comment_prefix("ko ")
+        .add_preserved_comment_prefix("/ko");
+
+    test_with_options(&options, &[(
+        "<!--[if IE]><p class='a'> IE </p><![endif]--><!--[if !IE]><!--> a \
+         <!--<![endif]--><!--#include file=\"a\" --> <!-- ko if: x --><!-- /ko -->",
+        INPUT,
+    )]);
+
+    // all conditional comments are kept and can be minified if comments are not removed
+    test_with_options(
+        &HTMLMinifierOptions::new().remove_comments(false).minify_conditional_comments(true),
+        &[(
+            "<!--[if IE]><p> x </p><![endif]--> <!-- a  b -->",
+            "<!--[if IE]>  <p>  x  </p>  <![endif]-->  <!-- a  b -->",
+        )],
+    );
+
+    // Knockout containerless bindings are kept in pairs
+    test_with_options(
+        &HTMLMinifierOptions::new()
+            .add_preserved_comment_prefix("ko ")
+            .add_preserved_comment_prefix("/ko"),
+        &[(
+            "<ul>\n<!-- ko foreach: items --><li data-bind=\"text: $data\"></li>\n<!-- /ko \
+             --></ul>",
+            "<ul>\n  <!-- ko foreach: items -->\n  <li data-bind=\"text: $data\"></li>\n  <!-- \
+             /ko -->\n</ul>",
+        )],
+    );
+
+    let mut html_minifier = HTMLMinifier::new();
+
+    html_minifier.set_comment_filter(|comment| comment.starts_with('!'));
+
+    html_minifier.digest(INPUT).unwrap();
+
+    assert_eq!(" a  <!--! MIT -->".as_bytes(), html_minifier.get_html());
+
+    // the diagnostics and warnings inside minified conditional comments are located in the whole input
+    const INPUT2: &str = "<p>\n<!--[if IE]><a id=x id=y><style>a{b:'c}</style><![endif]-->";
+
+    let mut html_minifier = HTMLMinifier::with_options(
+        HTMLMinifierOptions::new()
+            .keep_conditional_comments(true)
+            .minify_conditional_comments(true)
+            .embedded_error_policy(EmbeddedErrorPolicy::Warn)
+            .collect_diagnostics(true),
+    );
+
+    html_minifier.digest(INPUT2).unwrap();
+
+    let diagnostic = &html_minifier.get_diagnostics()[0];
+
+    assert_eq!(DiagnosticKind::DuplicateAttribute, diagnostic.kind);
+    assert_eq!(INPUT2.find("id=y").unwrap(), diagnostic.position.offset);
+    assert_eq!((2, 21), (diagnostic.position.line, diagnostic.position.column));
+    assert_eq!(
+        &["failed to minify the content of the `style` element at line 2, column 26: Unclosed \
+           string"],
+        html_minifier.get_warnings()
+    );
+}
@@ -174,7 +174,7 @@ fn start_tag_attribute_name_waiting_value() {
 #[test]
 fn start_tag_attribute_value_initial() {
     const CASES: [(&str, &str); 7] = [
-        ("<aaa abc/", "<aaa abc=/"),
+        ("<aaa abc=/", "<aaa abc=/"),
         ("<aaa abc>", "<aaa abc=>"),
         ("<aaa abc", "<aaa abc=\""),
         ("<aaa abc", "<aaa abc='"),
@@ -253,7 +253,11 @@ fn comment() {
 
     test_enabled_all_options(&CASES);
 
-    const CASES2: [(&str, &str); 2] = [("1<!---->23", "1<!---->23"), ("<!--123-->", "<!--123-->")];
+    const CASES2: [(&str, &str); 3] = [
+        ("1<!---->23", "1<!---->23"),
+        ("<!--123-->", "<!--123-->"),
+        ("1 <!--2-->3", "1  <!--2-->  3"),
+    ];
 
     test_disabled_all_options(&CASES2);
 }
@@ -262,12 +266,12 @@ fn comment() {
 fn script_default() {
     const CASES: [(&str, &str); 2] = [
         (
-            "<script type='application/ecmascript'>   alert('1234!')    ;   </script>",
-            "<script type='application/ecmascript'>   alert('1234!')    ;   </script>",
+            "<script type='text/plain'>   alert('1234!')    ;   </script>",
+            "<script type='text/plain'>   alert('1234!')    ;   </script>",
         ),
         (
-            "<script type='application/ecmascript'>   alert('1234!')    ;   </script>",
-            "<script type='application/ecmascript'>   alert('1234!')    ;   </script  >",
+            "<script type='text/plain'>   alert('1234!')    ;   </script>",
+            "<script type='text/plain'>   alert('1234!')    ;   </script  >",
         ),
     ];
 
@@ -288,6 +292,72 @@ fn script_javascript() {
     test_enabled_all_options(&CASES);
 }
 
+#[test]
+fn script_json() {
+    const CASES: [(&str, &str); 5] = [
+        (
+            "<script type='application/ld+json'>{\"@type\":\"Person\",\"name\":\"A  \
+             B\",\"knows\":[1,-2.5e+3,true,null,{}]}</script>",
+            "<script type='application/ld+json'>\n  {\n    \"@type\" : \"Person\",\n    \"name\": \
+             \"A  B\",\n    \"knows\": [ 1, -2.5e+3, true, null, { } ]\n  }\n</script>",
+        ),
+        (
+            "<script type='application/json'>[\"\\\" ]\\u00e9\"]</script>",
+            "<script type='application/json'> [ \"\\\" ]\\u00e9\" ] </script  >",
+        ),
+        (
+            "<script type=importmap>{\"imports\":{\"a\":\"/a.js\"}}</script>",
+            "<script type=importmap>{ \"imports\": { \"a\": \"/a.js\" } }</script>",
+        ),
+        (
+            "<script type='application/json'> { \"a\": 01 } </script>",
+            "<script type='application/json'> { \"a\": 01 } </script>",
+        ),
+        (
+            "<script type='application/json'> { \"a\": [1, 2 } </script>",
+            "<script type='application/json'> { \"a\": [1, 2 } </script  >",
+        ),
+    ];
+
+    test_enabled_all_options(&CASES);
+}
+
+#[test]
+fn script_language() {
+    const CASES: [(&str, &str); 7] = [
+        (
+            "<script language='JavaScript1.2'>alert('1234!');</script>",
+            "<script language='JavaScript1.2'>   alert('1234!')    ;   </script>",
+        ),
+        (
+            "<script language=vbscript>   MsgBox  \"1234!\"   </script>",
+            "<script language=vbscript>   MsgBox  \"1234!\"   </script  >",
+        ),
+        (
+            "<script language>alert('1234!');</script>",
+            "<script language=''>   alert('1234!')    ;   </script>",
+        ),
+        (
+            "<script type language=vbscript>alert('1234!');</script>",
+            "<script type='' language=vbscript>   alert('1234!')    ;   </script>",
+        ),
+        (
+            "<script type language=vbscript>alert('1234!');</script>",
+            "<script type language=vbscript>   alert('1234!')    ;   </script>",
+        ),
+        (
+            "<script language=vbscript type>alert('1234!');</script>",
+            "<script language=vbscript type>   alert('1234!')    ;   </script>",
+        ),
+        (
+            "<script type=text/plain language=javascript>   alert('1234!')    ;   </script>",
+            "<script type=text/plain language=javascript>   alert('1234!')    ;   </script>",
+        ),
+    ];
+
+    test_enabled_all_options(&CASES);
+}
+
 #[test]
 fn style_default() {
     const CASES: [(&str, &str); 2] = [
@@ -526,12 +596,12 @@ fn width_2_comment() {
 fn width_2_script_default() {
     const CASES: [(&str, &str); 2] = [
         (
-            "<script type='application/ecmascript'>   é é   </script>",
-            "<script type='application/ecmascript'>   é é   </script>",
+            "<script type='text/plain'>   é é   </script>",
+            "<script type='text/plain'>   é é   </script>",
         ),
         (
-            "<script type='application/ecmascript'>   é é   </script>",
-            "<script type='application/ecmascript'>   é é   </script  >",
+            "<script type='text/plain'>   é é   </script>",
+            "<script type='text/plain'>   é é   </script  >",
         ),
     ];
 
@@ -691,12 +761,12 @@ fn width_n_comment() {
 fn width_n_script_default() {
     const CASES: [(&str, &str); 2] = [
         (
-            "<script type='application/ecmascript'>   中 中   </script>",
-            "<script type='application/ecmascript'>   中 中   </script>",
+            "<script type='text/plain'>   中 中   </script>",
+            "<script type='text/plain'>   中 中   </script>",
         ),
         (
-            "<script type='application/ecmascript'>   中 中   </script>",
-            "<script type='application/ecmascript'>   中 中   </script  >",
+            "<script type='text/plain'>   中 中   </script>",
+            "<script type='text/plain'>   中 中   </script  >",
         ),
     ];
 